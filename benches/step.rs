@@ -0,0 +1,122 @@
+// Compares bit packed chunks against the 6x6 bool chunks they replaced,
+// one byte per cell storage and HashLife:
+// cargo bench --bench step
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use gol::{DenseWorld, HashLife, LifeEngine, Rule, World, CHUNK_SIZE};
+
+fn soup(engine: &mut dyn LifeEngine, size: i64) {
+    let mut seed = 0x2545F4914F6CDD1Du64;
+    for y in 0..size {
+        for x in 0..size {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            if seed % 100 < 35 {
+                engine.set_cell(x, y, true);
+            }
+        }
+    }
+}
+
+// Previous chunk layout, one bool per cell in 6x6 chunks stepped cell by
+// cell. Only knows B3/S23 like the code it stands in for
+const OLD_CHUNK_SIZE: i64 = 6;
+struct BoolChunks {
+    chunks: HashMap<(i64, i64), [bool; (OLD_CHUNK_SIZE * OLD_CHUNK_SIZE) as usize]>,
+}
+impl BoolChunks {
+    fn new() -> Self {
+        BoolChunks { chunks: HashMap::new() }
+    }
+    fn index(x: i64, y: i64) -> ((i64, i64), usize) {
+        let pos = (x.div_euclid(OLD_CHUNK_SIZE), y.div_euclid(OLD_CHUNK_SIZE));
+        (pos, (x.rem_euclid(OLD_CHUNK_SIZE) + y.rem_euclid(OLD_CHUNK_SIZE) * OLD_CHUNK_SIZE) as usize)
+    }
+    fn get(&self, x: i64, y: i64) -> bool {
+        let (pos, i) = BoolChunks::index(x, y);
+        self.chunks.get(&pos).is_some_and(|cells| cells[i])
+    }
+    fn set(&mut self, x: i64, y: i64) {
+        let (pos, i) = BoolChunks::index(x, y);
+        self.chunks.entry(pos).or_insert([false; (OLD_CHUNK_SIZE * OLD_CHUNK_SIZE) as usize])[i] = true;
+    }
+    fn step(&mut self) {
+        let mut next = BoolChunks::new();
+        let mut checked = HashSet::new();
+        for (pos, cells) in self.chunks.iter() {
+            for (i, _) in cells.iter().enumerate().filter(|cell| *cell.1) {
+                let x = pos.0 * OLD_CHUNK_SIZE + i as i64 % OLD_CHUNK_SIZE;
+                let y = pos.1 * OLD_CHUNK_SIZE + i as i64 / OLD_CHUNK_SIZE;
+                for (cx, cy) in neighbourhood(x, y) {
+                    if !checked.insert((cx, cy)) {
+                        continue;
+                    }
+                    let count = neighbourhood(cx, cy)
+                        .filter(|(nx, ny)| (*nx, *ny) != (cx, cy) && self.get(*nx, *ny))
+                        .count();
+                    if count == 3 || (count == 2 && self.get(cx, cy)) {
+                        next.set(cx, cy);
+                    }
+                }
+            }
+        }
+        *self = next;
+    }
+    fn population(&self) -> usize {
+        self.chunks.values().map(|cells| cells.iter().filter(|cell| **cell).count()).sum()
+    }
+}
+
+fn neighbourhood(x: i64, y: i64) -> impl Iterator<Item = (i64, i64)> {
+    (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+}
+
+fn bench_old_layout(generations: u32) {
+    let mut world = World::new();
+    soup(&mut world, 256);
+    let mut old = BoolChunks::new();
+    for (x, y, _) in world.live_cells() {
+        old.set(x, y);
+    }
+    let time = Instant::now();
+    for _ in 0..generations {
+        old.step();
+    }
+    let elapsed = time.elapsed();
+    println!(
+        "{:<9} {:<14} {:>8.3}ms/gen  population {}",
+        "BOOL 6x6",
+        "B3/S23",
+        elapsed.as_secs_f64() * 1000.0 / generations as f64,
+        old.population()
+    );
+}
+
+fn bench(mut engine: Box<dyn LifeEngine>, rule: &str, generations: u32) {
+    engine.set_rule(Rule::parse(rule).unwrap());
+    soup(engine.as_mut(), 256);
+    let time = Instant::now();
+    for _ in 0..generations {
+        engine.life_step();
+    }
+    let elapsed = time.elapsed();
+    println!(
+        "{:<9} {:<14} {:>8.3}ms/gen  population {}",
+        engine.name(),
+        rule,
+        elapsed.as_secs_f64() * 1000.0 / generations as f64,
+        engine.population()
+    );
+}
+
+fn main() {
+    println!("CHUNK_SIZE {}", CHUNK_SIZE);
+    bench_old_layout(100);
+    for rule in ["B3/S23", "B3/S2-i34q", "B2/S345/C4"] {
+        bench(Box::new(DenseWorld::new()), rule, 100);
+        bench(Box::new(World::new()), rule, 100);
+        bench(Box::new(HashLife::new()), rule, 100);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::rule::Rule;
+
+// Commands typed after ':' in the app
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    // Advance this many generations
+    Step(u64),
+    // Advance until the world is at this generation
+    Goto(u64),
+    Rule(Rule),
+    // Random cells, density in percent and a new seed when none is given
+    Soup { seed: Option<u64>, density: u8 },
+}
+impl Command {
+    pub fn parse(text: &str) -> Result<Command, String> {
+        let mut words = text.split_whitespace();
+        let name = words.next().ok_or("empty command")?;
+        let args: Vec<&str> = words.collect();
+        let max_args = match name {
+            "soup" => 2,
+            _ => 1,
+        };
+        if args.len() > max_args {
+            return Err(format!("too many arguments to {}", name));
+        }
+        let arg = args.first().copied();
+        let number = |arg: Option<&str>| -> Result<u64, String> {
+            let arg = arg.ok_or(format!("{} needs a number", name))?;
+            arg.replace('_', "")
+                .parse()
+                .map_err(|_| format!("not a number: {}", arg))
+        };
+        match name {
+            "step" | "s" => Ok(Command::Step(number(arg)?)),
+            "goto" | "g" => Ok(Command::Goto(number(arg)?)),
+            "rule" => Ok(Command::Rule(Rule::parse(arg.ok_or("rule needs a rule string")?)?)),
+            "soup" => {
+                let seed = arg.map(|arg| number(Some(arg))).transpose()?;
+                let density = match args.get(1) {
+                    Some(arg) => arg
+                        .trim_end_matches('%')
+                        .parse()
+                        .ok()
+                        .filter(|density| *density <= 100)
+                        .ok_or(format!("density must be 0 to 100: {}", arg))?,
+                    None => 50,
+                };
+                Ok(Command::Soup { seed, density })
+            }
+            _ => Err(format!("unknown command: {}", name)),
+        }
+    }
+}
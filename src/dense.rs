@@ -0,0 +1,153 @@
+use std::time::{Duration, Instant};
+
+use crate::engine::{bounding_box, drop_states};
+use crate::math::*;
+use crate::rule::Rule;
+
+// Plain grid over the bounding box of the pattern, every cell is computed
+// every generation. Slow but simple enough to check other engines against
+pub struct DenseWorld {
+    bounds: Vec4<i64>,
+    cells: Vec<u8>,
+    rule: Rule,
+    generation: u64,
+}
+impl DenseWorld {
+    pub fn new() -> Self {
+        DenseWorld {
+            bounds: Vec4::new(),
+            cells: Vec::new(),
+            rule: Rule::life(),
+            generation: 0,
+        }
+    }
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+    pub fn set_rule(&mut self, rule: Rule) {
+        drop_states(self, rule);
+        self.rule = rule;
+    }
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+    pub fn size(&self) -> Vec4<i64> {
+        self.bounds
+    }
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        let b = &self.bounds;
+        if x < b.x1 || x >= b.x2 || y < b.y1 || y >= b.y2 {
+            return None;
+        }
+        Some(((x - b.x1) + (y - b.y1) * (b.x2 - b.x1)) as usize)
+    }
+    pub fn get_cell(&self, x: i64, y: i64) -> u8 {
+        self.index(x, y).map_or(0, |i| self.cells[i])
+    }
+    fn resize(&mut self, bounds: Vec4<i64>) {
+        let size = bounds.size();
+        let mut cells = vec![0; (size.x * size.y) as usize];
+        for y in bounds.y1..bounds.y2 {
+            for x in bounds.x1..bounds.x2 {
+                cells[((x - bounds.x1) + (y - bounds.y1) * size.x) as usize] = self.get_cell(x, y);
+            }
+        }
+        self.bounds = bounds;
+        self.cells = cells;
+    }
+    pub fn set_cell(&mut self, x: i64, y: i64, state: bool) {
+        self.set_state(x, y, state as u8);
+    }
+    pub fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        if self.index(x, y).is_none() {
+            if state == 0 {
+                return;
+            }
+            let b = self.bounds;
+            let bounds = if self.cells.is_empty() {
+                Vec4 { x1: x, y1: y, x2: x + 1, y2: y + 1 }
+            } else {
+                Vec4 {
+                    x1: b.x1.min(x),
+                    y1: b.y1.min(y),
+                    x2: b.x2.max(x + 1),
+                    y2: b.y2.max(y + 1),
+                }
+            };
+            self.resize(bounds);
+        }
+        let i = self.index(x, y).unwrap();
+        self.cells[i] = state;
+    }
+    pub fn get_world(&self, win: Vec4<i64>) -> (Vec<u8>, Duration) {
+        let time = Instant::now();
+        let win_size = win.size();
+        let mut data = vec![0; (win_size.x * win_size.y) as usize];
+        for y in win.y1..win.y2 {
+            for x in win.x1..win.x2 {
+                data[((x - win.x1) + (y - win.y1) * win_size.x) as usize] = self.get_cell(x, y);
+            }
+        }
+        (data, time.elapsed())
+    }
+    pub fn life_step(&mut self) {
+        self.generation += 1;
+        if self.cells.is_empty() {
+            return;
+        }
+        // Pattern can grow by one cell to every side
+        let b = self.bounds;
+        let bounds = Vec4 { x1: b.x1 - 1, y1: b.y1 - 1, x2: b.x2 + 1, y2: b.y2 + 1 };
+        let mut next = DenseWorld {
+            bounds,
+            cells: vec![0; ((bounds.x2 - bounds.x1) * (bounds.y2 - bounds.y1)) as usize],
+            rule: self.rule,
+            generation: self.generation,
+        };
+        for y in bounds.y1..bounds.y2 {
+            for x in bounds.x1..bounds.x2 {
+                let mut mask = 0;
+                for i in 0..8 {
+                    let dir = index2dir(i);
+                    if self.get_cell(x + dir.0 as i64, y + dir.1 as i64) == 1 {
+                        mask |= 1 << i;
+                    }
+                }
+                let i = next.index(x, y).unwrap();
+                next.cells[i] = self.rule.next(self.get_cell(x, y), mask);
+            }
+        }
+        // Crop to living cells
+        match next.bounding_box() {
+            Some(bounds) => next.resize(bounds),
+            None => next = DenseWorld { rule: self.rule, generation: self.generation, ..DenseWorld::new() },
+        }
+        *self = next;
+    }
+    pub fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut cells = Vec::new();
+        for y in self.bounds.y1..self.bounds.y2 {
+            for x in self.bounds.x1..self.bounds.x2 {
+                let state = self.get_cell(x, y);
+                if state != 0 {
+                    cells.push((x, y, state));
+                }
+            }
+        }
+        cells
+    }
+    pub fn memory(&self) -> usize {
+        self.cells.capacity()
+    }
+    pub fn bounding_box(&self) -> Option<Vec4<i64>> {
+        bounding_box(&self.live_cells())
+    }
+}
+impl Default for DenseWorld {
+    fn default() -> Self {
+        DenseWorld::new()
+    }
+}
@@ -0,0 +1,303 @@
+use std::time::Duration;
+
+use crate::dense::DenseWorld;
+use crate::gol::World;
+use crate::hashlife::HashLife;
+use crate::math::*;
+use crate::rule::Rule;
+use crate::stats::Stats;
+
+// Common surface of all simulation backends, Send so a world can be
+// moved to a background task
+pub trait LifeEngine: Send {
+    fn name(&self) -> &'static str;
+    fn rule(&self) -> Rule;
+    fn set_rule(&mut self, rule: Rule);
+    // Generations stepped since the world was created
+    fn generation(&self) -> u64;
+    fn set_generation(&mut self, generation: u64);
+    fn size(&self) -> Vec4<i64>;
+    fn get_cell(&self, x: i64, y: i64) -> u8;
+    fn set_state(&mut self, x: i64, y: i64, state: u8);
+    fn set_cell(&mut self, x: i64, y: i64, state: bool) {
+        self.set_state(x, y, state as u8);
+    }
+    // Dead cells come alive, any other state dies. Returns the new state
+    fn toggle_cell(&mut self, x: i64, y: i64) -> u8 {
+        let state = (self.get_cell(x, y) == 0) as u8;
+        self.set_state(x, y, state);
+        state
+    }
+    fn get_world(&self, win: Vec4<i64>) -> (Vec<u8>, Duration);
+    fn life_step(&mut self);
+    // Advance 2^n generations, false without stepping when that many
+    // can't be counted
+    fn step_pow2(&mut self, n: u8) -> bool {
+        if n >= 64 {
+            return false;
+        }
+        for _ in 0..1u64 << n {
+            self.life_step();
+        }
+        true
+    }
+    // Whether step_pow2 is much cheaper than stepping one by one
+    fn fast_pow2(&self) -> bool {
+        false
+    }
+    // Engines without history have nothing to undo
+    fn undo(&mut self) -> bool {
+        false
+    }
+    fn redo(&mut self) -> bool {
+        false
+    }
+    fn commit_edit(&mut self) {}
+    // Take cells, rule and generation of another engine as one change
+    // that can be undone, false for engines without history
+    fn replace(&mut self, _other: &dyn LifeEngine) -> bool {
+        false
+    }
+    fn set_history(&mut self, _enabled: bool) {}
+    fn clear_history(&mut self) {}
+    // Quadtree of engines that have one, saves converting for macrocells
+    fn quadtree(&self) -> Option<&HashLife> {
+        None
+    }
+    // Step statistics of engines that keep them
+    fn stats(&self) -> Option<&Stats> {
+        None
+    }
+    // Every cell with non zero state
+    fn live_cells(&self) -> Vec<(i64, i64, u8)>;
+    fn population(&self) -> u64 {
+        self.live_cells().len() as u64
+    }
+    fn bounding_box(&self) -> Option<Vec4<i64>> {
+        bounding_box(&self.live_cells())
+    }
+    fn memory(&self) -> MemoryStats;
+}
+
+// Storage used by an engine. Blocks are chunks, quadtree nodes or
+// nothing depending on the engine
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct MemoryStats {
+    pub bytes: usize,
+    pub blocks: usize,
+}
+
+pub fn bounding_box(cells: &[(i64, i64, u8)]) -> Option<Vec4<i64>> {
+    let first = cells.first()?;
+    let mut rect = Vec4 { x1: first.0, y1: first.1, x2: first.0 + 1, y2: first.1 + 1 };
+    for (x, y, _) in cells {
+        rect.x1 = rect.x1.min(*x);
+        rect.y1 = rect.y1.min(*y);
+        rect.x2 = rect.x2.max(x + 1);
+        rect.y2 = rect.y2.max(y + 1);
+    }
+    Some(rect)
+}
+
+// Kill cells in states a rule with fewer states doesn't have, called by
+// set_rule before switching
+pub(crate) fn drop_states(engine: &mut dyn LifeEngine, rule: Rule) {
+    if rule.states() >= engine.rule().states() {
+        return;
+    }
+    for (x, y, state) in engine.live_cells() {
+        if state >= rule.states() {
+            engine.set_state(x, y, 0);
+        }
+    }
+}
+
+// Copy rule and cells into another engine
+pub fn transfer(from: &dyn LifeEngine, to: &mut dyn LifeEngine) {
+    to.set_rule(from.rule());
+    to.set_generation(from.generation());
+    for (x, y, state) in from.live_cells() {
+        to.set_state(x, y, state);
+    }
+}
+
+pub fn engine_by_name(name: &str) -> Option<Box<dyn LifeEngine>> {
+    match name {
+        "CHUNKS" => Some(Box::new(World::new())),
+        "HASHLIFE" => Some(Box::new(HashLife::new())),
+        "DENSE" => Some(Box::new(DenseWorld::new())),
+        _ => None,
+    }
+}
+
+// Empty engine that comes after the given one when cycling in App
+pub fn next_engine(engine: &dyn LifeEngine) -> Box<dyn LifeEngine> {
+    match engine.name() {
+        "CHUNKS" => Box::new(HashLife::new()),
+        "HASHLIFE" => Box::new(DenseWorld::new()),
+        _ => Box::new(World::new()),
+    }
+}
+
+impl LifeEngine for World {
+    fn name(&self) -> &'static str {
+        "CHUNKS"
+    }
+    fn rule(&self) -> Rule {
+        World::rule(self)
+    }
+    fn set_rule(&mut self, rule: Rule) {
+        World::set_rule(self, rule)
+    }
+    fn generation(&self) -> u64 {
+        World::generation(self)
+    }
+    fn set_generation(&mut self, generation: u64) {
+        World::set_generation(self, generation)
+    }
+    fn size(&self) -> Vec4<i64> {
+        World::size(self)
+    }
+    fn get_cell(&self, x: i64, y: i64) -> u8 {
+        World::get_cell(self, x, y)
+    }
+    fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        World::set_state(self, x, y, state)
+    }
+    fn get_world(&self, win: Vec4<i64>) -> (Vec<u8>, Duration) {
+        World::get_world(self, win)
+    }
+    fn life_step(&mut self) {
+        World::life_step(self)
+    }
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        World::live_cells(self)
+    }
+    fn population(&self) -> u64 {
+        World::chunk_population(self)
+    }
+    fn bounding_box(&self) -> Option<Vec4<i64>> {
+        World::chunk_bounding_box(self)
+    }
+    fn stats(&self) -> Option<&Stats> {
+        Some(World::stats(self))
+    }
+    fn undo(&mut self) -> bool {
+        World::undo(self)
+    }
+    fn redo(&mut self) -> bool {
+        World::redo(self)
+    }
+    fn commit_edit(&mut self) {
+        World::commit_edit(self)
+    }
+    fn replace(&mut self, other: &dyn LifeEngine) -> bool {
+        World::replace(self, other.generation(), other.rule(), &other.live_cells());
+        true
+    }
+    fn set_history(&mut self, enabled: bool) {
+        World::set_history(self, enabled)
+    }
+    fn clear_history(&mut self) {
+        World::clear_history(self)
+    }
+    fn memory(&self) -> MemoryStats {
+        MemoryStats { bytes: World::memory(self), blocks: self.chunk_count() }
+    }
+}
+
+impl LifeEngine for HashLife {
+    fn name(&self) -> &'static str {
+        "HASHLIFE"
+    }
+    fn rule(&self) -> Rule {
+        HashLife::rule(self)
+    }
+    fn set_rule(&mut self, rule: Rule) {
+        HashLife::set_rule(self, rule)
+    }
+    fn generation(&self) -> u64 {
+        HashLife::generation(self)
+    }
+    fn set_generation(&mut self, generation: u64) {
+        HashLife::set_generation(self, generation)
+    }
+    fn size(&self) -> Vec4<i64> {
+        HashLife::size(self)
+    }
+    fn get_cell(&self, x: i64, y: i64) -> u8 {
+        HashLife::get_cell(self, x, y)
+    }
+    fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        HashLife::set_state(self, x, y, state)
+    }
+    fn get_world(&self, win: Vec4<i64>) -> (Vec<u8>, Duration) {
+        HashLife::get_world(self, win)
+    }
+    fn life_step(&mut self) {
+        HashLife::life_step(self)
+    }
+    fn step_pow2(&mut self, n: u8) -> bool {
+        HashLife::step_pow2(self, n)
+    }
+    fn fast_pow2(&self) -> bool {
+        true
+    }
+    fn quadtree(&self) -> Option<&HashLife> {
+        Some(self)
+    }
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        HashLife::live_cells(self)
+    }
+    fn population(&self) -> u64 {
+        HashLife::population(self)
+    }
+    fn bounding_box(&self) -> Option<Vec4<i64>> {
+        HashLife::bounding_box(self)
+    }
+    fn memory(&self) -> MemoryStats {
+        MemoryStats { bytes: HashLife::memory(self), blocks: self.node_count() }
+    }
+}
+
+impl LifeEngine for DenseWorld {
+    fn name(&self) -> &'static str {
+        "DENSE"
+    }
+    fn rule(&self) -> Rule {
+        DenseWorld::rule(self)
+    }
+    fn set_rule(&mut self, rule: Rule) {
+        DenseWorld::set_rule(self, rule)
+    }
+    fn generation(&self) -> u64 {
+        DenseWorld::generation(self)
+    }
+    fn set_generation(&mut self, generation: u64) {
+        DenseWorld::set_generation(self, generation)
+    }
+    fn size(&self) -> Vec4<i64> {
+        DenseWorld::size(self)
+    }
+    fn get_cell(&self, x: i64, y: i64) -> u8 {
+        DenseWorld::get_cell(self, x, y)
+    }
+    fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        DenseWorld::set_state(self, x, y, state)
+    }
+    fn get_world(&self, win: Vec4<i64>) -> (Vec<u8>, Duration) {
+        DenseWorld::get_world(self, win)
+    }
+    fn life_step(&mut self) {
+        DenseWorld::life_step(self)
+    }
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        DenseWorld::live_cells(self)
+    }
+    fn bounding_box(&self) -> Option<Vec4<i64>> {
+        DenseWorld::bounding_box(self)
+    }
+    fn memory(&self) -> MemoryStats {
+        MemoryStats { bytes: DenseWorld::memory(self), blocks: 0 }
+    }
+}
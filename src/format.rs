@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use crate::hashlife::HashLife;
+use crate::life::*;
+use crate::macrocell::*;
+use crate::pattern::Pattern;
+use crate::plaintext::*;
+use crate::rle::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+    Macrocell,
+}
+impl Format {
+    // Guess format from the first lines of a file
+    pub fn detect(text: &str) -> Format {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        let first = match lines.next() {
+            Some(line) => line,
+            None => return Format::Rle,
+        };
+        if first.starts_with("#Life 1.06") {
+            return Format::Life106;
+        }
+        if first.starts_with("#Life 1.05") {
+            return Format::Life105;
+        }
+        if first.starts_with("[M2]") {
+            return Format::Macrocell;
+        }
+        if first.starts_with('!') {
+            return Format::Plaintext;
+        }
+        let data: Vec<&str> = std::iter::once(first)
+            .chain(lines)
+            .filter(|line| !line.starts_with('#'))
+            .collect();
+        let coordinates = |line: &&str| {
+            let numbers: Vec<&str> = line.split_whitespace().collect();
+            numbers.len() == 2 && numbers.iter().all(|n| n.parse::<i64>().is_ok())
+        };
+        if !data.is_empty() && data.iter().all(coordinates) {
+            return Format::Life106;
+        }
+        if !data.is_empty() && data.iter().all(|line| line.chars().all(|c| matches!(c, '.' | 'O' | '*'))) {
+            return Format::Plaintext;
+        }
+        Format::Rle
+    }
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "rle" => Some(Format::Rle),
+            "cells" => Some(Format::Plaintext),
+            "lif" | "life" => Some(Format::Life106),
+            "mc" => Some(Format::Macrocell),
+            _ => None,
+        }
+    }
+    pub fn read(self, text: &str) -> Result<Pattern, String> {
+        match self {
+            Format::Rle => read_rle(text),
+            Format::Plaintext => read_cells(text),
+            Format::Life105 => read_life105(text),
+            Format::Life106 => read_life106(text),
+            Format::Macrocell => Ok(Pattern::from_engine(&read_macrocell(text)?)),
+        }
+    }
+    // Life 1.05 is only read, Life 1.06 is written instead
+    pub fn write(self, pattern: &Pattern) -> String {
+        match self {
+            Format::Rle => write_rle(pattern),
+            Format::Plaintext => write_cells(pattern),
+            Format::Life105 | Format::Life106 => write_life106(pattern),
+            Format::Macrocell => {
+                let mut life = HashLife::new();
+                if let Some(rule) = pattern.rule {
+                    life.set_rule(rule);
+                }
+                pattern.place(&mut life, 0, 0);
+                write_macrocell(&life)
+            }
+        }
+    }
+}
+
+// Pattern in any known format
+pub fn read_pattern(text: &str) -> Result<Pattern, String> {
+    Format::detect(text).read(text)
+}
+
+pub fn read_file(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+pub fn load_pattern(path: &Path) -> Result<Pattern, String> {
+    read_pattern(&read_file(path)?).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+// Format is chosen by extension, RLE if there is none
+pub fn save_pattern(path: &Path, pattern: &Pattern) -> Result<(), String> {
+    let format = Format::from_extension(path).unwrap_or(Format::Rle);
+    std::fs::write(path, format.write(pattern)).map_err(|err| format!("{}: {}", path.display(), err))
+}
@@ -1,117 +1,302 @@
-use std::{cell::{RefCell, Cell}, rc::Rc, vec, time::{Instant, Duration}, collections::HashMap, borrow::BorrowMut};
+use std::{vec, thread, mem, time::{Instant, Duration}, collections::{HashMap, HashSet}};
 
+use crate::engine::drop_states;
+use crate::history::*;
 use crate::math::*;
+use crate::rule::Rule;
+use crate::soup::place_soup;
+use crate::stats::*;
 
-const CHUNK_SIZE: i16 = 6;
-struct Cells([bool; (CHUNK_SIZE * CHUNK_SIZE) as usize]);
+// Width and height of a chunk, anything from 1 to 64 cells as a row of
+// cells is packed into one u64
+pub const CHUNK_SIZE: i16 = 64;
+const _: () = assert!(CHUNK_SIZE > 0 && CHUNK_SIZE <= 64);
+const ROWS: usize = CHUNK_SIZE as usize;
+const ROW_MASK: u64 = u64::MAX >> (64 - CHUNK_SIZE);
+// Fewer chunks than this per thread are not worth spawning for
+const CHUNKS_PER_THREAD: usize = 4;
+// Empty chunks are dropped after this many generations by default
+const GC_IDLE: u32 = 16;
+// World rectangle while nothing is alive
+const EMPTY_SIZE: Vec4<i64> = Vec4 {
+    x1: 0,
+    y1: 0,
+    x2: CHUNK_SIZE as i64,
+    y2: CHUNK_SIZE as i64,
+};
+
+#[derive(Clone)]
+struct Cells {
+    // Bit x of row y is set for alive cells
+    rows: [u64; ROWS],
+    // Cells in decay states of Generations rules, states are only
+    // allocated while there are any
+    dying: [u64; ROWS],
+    states: Vec<u8>,
+}
 impl Cells {
-    fn get(&self, x: i16, y: i16) -> bool {
-        self.0[x as usize + (y  * CHUNK_SIZE) as usize]
+    fn get(&self, x: i16, y: i16) -> u8 {
+        if self.rows[y as usize] >> x & 1 == 1 {
+            1
+        } else if self.dying[y as usize] >> x & 1 == 1 {
+            self.states[x as usize + y as usize * ROWS]
+        } else {
+            0
+        }
     }
-    fn set(&mut self, x: usize, y: usize, val: bool) {
-        self.0[x + (y * CHUNK_SIZE as usize)] = val;
+    fn set(&mut self, x: usize, y: usize, val: u8) {
+        let bit = 1 << x;
+        self.rows[y] &= !bit;
+        self.dying[y] &= !bit;
+        match val {
+            0 => {}
+            1 => self.rows[y] |= bit,
+            _ => {
+                if self.states.is_empty() {
+                    self.states = vec![0; ROWS * ROWS];
+                }
+                self.states[x + y * ROWS] = val;
+                self.dying[y] |= bit;
+            }
+        }
     }
     fn new() -> Self{
-        // let mut cells = [false; (CHUNK_SIZE*CHUNK_SIZE)as usize];
-        // for x in 0..CHUNK_SIZE{
-        //     for y in 0..CHUNK_SIZE{
-        //         if x == 0 || y == 0 || x == CHUNK_SIZE-1 || y == CHUNK_SIZE-1{
-        //             cells[(x + y*CHUNK_SIZE) as usize] = true;
-        //         }
-        //     }
-        // }
-        // Cells(cells)
-        Cells([false; (CHUNK_SIZE*CHUNK_SIZE) as usize])
+        Cells {
+            rows: [0; ROWS],
+            dying: [0; ROWS],
+            states: Vec::new(),
+        }
+    }
+    fn is_empty(&self) -> bool {
+        self.rows.iter().chain(self.dying.iter()).all(|row| *row == 0)
+    }
+    fn has_dying(&self) -> bool {
+        self.dying.iter().any(|row| *row != 0)
+    }
+    fn population(&self) -> u64 {
+        self.rows.iter().zip(self.dying.iter()).map(|(row, dying)| (row | dying).count_ones() as u64).sum()
+    }
+    // Cells that are alive here and dead in other
+    fn count_missing(&self, other: &Cells) -> u64 {
+        (0..ROWS)
+            .map(|y| ((self.rows[y] | self.dying[y]) & !(other.rows[y] | other.dying[y])).count_ones() as u64)
+            .sum()
     }
 }
+
+// Alive rows of a chunk with one cell wide frame from its neighbours.
+// Bit 0 is x = -1 and row 0 is y = -1
+type Frame = [u128; ROWS + 2];
+
+// Outer totalistic two state rules, all cells of a row at once. Neighbour
+// counts are summed into four bit planes and compared to the rule
+fn step_packed(birth: u16, survival: u16, frame: &Frame) -> Cells {
+    let mut cells = Cells::new();
+    for y in 0..ROWS {
+        let (up, mid, down) = (frame[y], frame[y + 1], frame[y + 2]);
+        let neighbours = [up << 1, up, up >> 1, mid << 1, mid >> 1, down << 1, down, down >> 1];
+        let mut planes = [0u128; 4];
+        for n in neighbours {
+            let mut carry = n;
+            for plane in planes.iter_mut() {
+                let next_carry = *plane & carry;
+                *plane ^= carry;
+                carry = next_carry;
+            }
+        }
+        let mut next = 0u128;
+        for count in 0..=8 {
+            let born = birth >> count & 1 == 1;
+            let survives = survival >> count & 1 == 1;
+            if !born && !survives {
+                continue;
+            }
+            let mut equal = !0u128;
+            for (bit, plane) in planes.iter().enumerate() {
+                equal &= if count >> bit & 1 == 1 { *plane } else { !*plane };
+            }
+            if born {
+                next |= equal & !mid;
+            }
+            if survives {
+                next |= equal & mid;
+            }
+        }
+        cells.rows[y] = (next >> 1) as u64 & ROW_MASK;
+    }
+    cells
+}
+
+// Any rule, cell by cell but only where something can happen
+fn step_cells(rule: &Rule, frame: &Frame, old: &Cells) -> Cells {
+    let mut cells = Cells::new();
+    for y in 0..ROWS {
+        let near = frame[y] | frame[y + 1] | frame[y + 2];
+        let mut candidates = ((near << 1 | near | near >> 1) >> 1) as u64 & ROW_MASK | old.dying[y];
+        while candidates != 0 {
+            let x = candidates.trailing_zeros() as i16;
+            candidates &= candidates - 1;
+            let mut mask = 0;
+            for i in 0..8 {
+                let dir = index2dir(i);
+                let row = frame[(y as i16 + 1 + dir.1) as usize];
+                if row >> (x + 1 + dir.0) & 1 == 1 {
+                    mask |= 1 << i;
+                }
+            }
+            let state = rule.next(old.get(x, y as i16), mask);
+            if state != 0 {
+                cells.set(x as usize, y, state);
+            }
+        }
+    }
+    cells
+}
+
 pub struct CellChunk {
-    border: RefCell<[Option<Rc<CellChunk>>; 8]>,
-    cells: RefCell<Cells>,
-    alive : RefCell<HashMap<(i16, i16), ()>>,
-    check: RefCell<HashMap<(i16, i16), ()>>
+    cells: Cells,
+    // Generations the chunk has been empty for
+    idle: u32,
 }
 impl CellChunk {
     pub fn new() -> Self {
         CellChunk {
-            border: RefCell::new(CellChunk::empty_chunks()),
-            cells: RefCell::new(Cells::new()),
-            alive : RefCell::new(HashMap::new()),
-            check: RefCell::new(HashMap::new())
+            cells: Cells::new(),
+            idle: 0,
         }
     }
-    fn empty_chunks() -> [Option<Rc<CellChunk>>; 8] {
-        [
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-        ]
-    }
-    pub fn chunk_to(&self, x: i64, y: i64) -> Rc<CellChunk> {
-        self.border.borrow()[dir2index(x, y)]
-            .as_ref()
-            .unwrap()
-            .clone()
-    }
-    pub fn get_cell(&self, x: i16, y:i16) -> bool{
-        if x < 0 || x >= CHUNK_SIZE as i16 || y < 0 || y >= CHUNK_SIZE as i16{
-            // direction to neighbour chunk
-            let chunk_x = if x < 0 || x >= CHUNK_SIZE as i16 {x.signum() as i64} else {0};
-            let chunk_y = if y < 0 || y >= CHUNK_SIZE as i16 {y.signum() as i64} else {0};
-            let cell_x = if chunk_x == 0 {x} else {CHUNK_SIZE-1*(chunk_x == -1) as i16};
-            let cell_y = if chunk_y == 0 {y} else {CHUNK_SIZE-1*(chunk_y == -1) as i16};
-            // data
-            return self.chunk_to(chunk_x, chunk_y).cells.borrow().get(cell_x, cell_y);
-        }else{
-            return self.cells.borrow().get(x, y);
-        }
-    }
-    pub fn set_check(&self, x: i16, y: i16) -> Option<((i64, i64), Rc<CellChunk>)>{
-        if x < 0 || x >= CHUNK_SIZE as i16 || y < 0 || y >= CHUNK_SIZE as i16{
-            // direction to neighbour chunk
-            let chunk_x = if x < 0 || x >= CHUNK_SIZE as i16 {x.signum() as i64} else {0};
-            let chunk_y = if y < 0 || y >= CHUNK_SIZE as i16 {y.signum() as i64} else {0};
-            let cell_x = if chunk_x == 0 {x} else {CHUNK_SIZE-1*(chunk_x == -1) as i16};
-            let cell_y = if chunk_y == 0 {y} else {CHUNK_SIZE-1*(chunk_y == -1) as i16};
-            let chunk = self.chunk_to(chunk_x, chunk_y);
-            chunk.check.borrow_mut().insert((cell_x, cell_y), ());
-            return Some(((chunk_x as i64, chunk_y as i64), chunk));
-        }else{
-            self.check.borrow_mut().insert((x, y), ());
-            return None; 
-        }
+}
+impl Default for CellChunk {
+    fn default() -> Self {
+        CellChunk::new()
     }
-
 }
 pub struct World {
     size: Vec4<i64>,
-    root: Rc<CellChunk>,
-    alive_chunks: HashMap<(i64, i64), (Rc<CellChunk>)>
+    chunks: HashMap<(i64, i64), CellChunk>,
+    alive_chunks: HashSet<(i64, i64)>,
+    rule: Rule,
+    threads: usize,
+    gc_idle: u32,
+    generation: u64,
+    history: History,
+    stats: Stats,
 }
 impl World {
     pub fn new() -> Self {
         World {
-            size: Vec4 {
-                x1: 0,
-                y1: 0,
-                x2: CHUNK_SIZE as i64,
-                y2: CHUNK_SIZE as i64,
-            },
-            root: Rc::new(CellChunk::new()),
-            alive_chunks: HashMap::new()
+            size: EMPTY_SIZE,
+            chunks: HashMap::new(),
+            alive_chunks: HashSet::new(),
+            rule: Rule::life(),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            gc_idle: GC_IDLE,
+            generation: 0,
+            history: History::new(),
+            stats: Stats::new(),
         }
     }
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+    pub fn set_rule(&mut self, rule: Rule) {
+        if self.history.enabled && rule != self.rule {
+            // Cells the new rule drops are an edit of their own
+            self.commit_edit();
+            drop_states(self, rule);
+            self.commit_edit();
+            self.history.push(Change::Rule(self.rule, rule));
+            self.history.redo.clear();
+        } else {
+            drop_states(self, rule);
+        }
+        self.rule = rule;
+    }
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+    // Threads used by life_step, 1 steps on the calling thread
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+    // Generations an empty chunk is kept around in case something
+    // comes back to it, 0 drops it right away
+    pub fn set_gc_idle(&mut self, generations: u32) {
+        self.gc_idle = generations;
+    }
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+    pub fn alive_chunk_count(&self) -> usize {
+        self.alive_chunks.len()
+    }
+    // Live cells counted from the bits of each alive chunk
+    pub(crate) fn chunk_population(&self) -> u64 {
+        self.alive_chunks.iter().map(|pos| self.chunks[pos].cells.population()).sum()
+    }
+    // Smallest rectangle holding every live cell, from the bits of each
+    // alive chunk without listing cells
+    pub(crate) fn chunk_bounding_box(&self) -> Option<Vec4<i64>> {
+        let mut rect: Option<Vec4<i64>> = None;
+        for pos in self.alive_chunks.iter() {
+            let cells = &self.chunks[pos].cells;
+            let mut columns = 0;
+            let (mut top, mut bottom) = (None, 0);
+            for y in 0..ROWS {
+                let row = cells.rows[y] | cells.dying[y];
+                if row != 0 {
+                    columns |= row;
+                    top.get_or_insert(y);
+                    bottom = y;
+                }
+            }
+            let top = match top {
+                Some(top) => top as i64,
+                None => continue,
+            };
+            let (x, y) = (pos.0 * CHUNK_SIZE as i64, pos.1 * CHUNK_SIZE as i64);
+            let chunk = Vec4 {
+                x1: x + columns.trailing_zeros() as i64,
+                y1: y + top,
+                x2: x + 64 - columns.leading_zeros() as i64,
+                y2: y + bottom as i64 + 1,
+            };
+            rect = Some(match rect {
+                Some(rect) => Vec4 {
+                    x1: rect.x1.min(chunk.x1),
+                    y1: rect.y1.min(chunk.y1),
+                    x2: rect.x2.max(chunk.x2),
+                    y2: rect.y2.max(chunk.y2),
+                },
+                None => chunk,
+            });
+        }
+        rect
+    }
+    // Births and deaths of the last step and recent populations
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+    // Approximate heap used by chunk storage
+    pub fn memory(&self) -> usize {
+        let states: usize = self.chunks.values().map(|chunk| chunk.cells.states.capacity()).sum();
+        self.chunks.capacity() * mem::size_of::<((i64, i64), CellChunk)>()
+            + self.alive_chunks.capacity() * mem::size_of::<(i64, i64)>()
+            + states
+    }
 
-    pub fn get_world(&self, win: Vec4<i64>) -> (Vec<bool>, Duration) {
+    pub fn get_world(&self, win: Vec4<i64>) -> (Vec<u8>, Duration) {
         // Time
         let time = Instant::now();
         // Data
         let win_size = win.size();
-        let mut data = vec![false; (win_size.x * win_size.y) as usize];
+        let mut data = vec![0; (win_size.x * win_size.y) as usize];
         // Calculate where window intersects with living world
         let contact = win.intersect(&self.size);
         if contact.is_none() {
@@ -119,30 +304,19 @@ impl World {
         }
         let contact = contact.unwrap();
         // Calculate needed chunks
-        let chunk_x_start = if contact.x1 != 0 {
-            (contact.x1 as f64 / CHUNK_SIZE as f64).floor() as i64
-        } else {
-            0
-        };
-        let chunk_x_end = if contact.x2 != 0 {
-            ((contact.x2 - 1) as f64 / CHUNK_SIZE as f64) as i64
-        } else {
-            0
-        };
-        let chunk_y_start = if contact.y1 != 0 {
-            (contact.y1 as f64 / CHUNK_SIZE as f64).floor() as i64
-        } else {
-            0
-        };
-        let chunk_y_end = if contact.y2 != 0 {
-            ((contact.y2 - 1) as f64 / CHUNK_SIZE as f64) as i64
-        } else {
-            0
-        };
+        let chunk_size = CHUNK_SIZE as i64;
+        let chunk_x_start = contact.x1.div_euclid(chunk_size);
+        let chunk_x_end = (contact.x2 - 1).div_euclid(chunk_size);
+        let chunk_y_start = contact.y1.div_euclid(chunk_size);
+        let chunk_y_end = (contact.y2 - 1).div_euclid(chunk_size);
         // Iterate over chunks
         for chunk_y in chunk_y_start..=chunk_y_end {
             for chunk_x in chunk_x_start..=chunk_x_end {
-                let chunk_size = CHUNK_SIZE as i64;
+                // Get chunk cells, missing chunks are empty
+                let chunk = match self.get_chunk(chunk_x, chunk_y) {
+                    Some(chunk) => chunk,
+                    None => continue,
+                };
                 let chunk_rect = Vec4 {
                     x1: chunk_x * chunk_size,
                     x2: chunk_x * chunk_size + chunk_size,
@@ -155,16 +329,13 @@ impl World {
                     continue;
                 }
                 let needed = needed.unwrap();
-                // Get chunk cells
-                let chunk = self.get_chunk(chunk_x, chunk_y);
-                let cells = chunk.cells.borrow();
                 // Iterate over needed cells and inject them to buffer
                 for x in needed.x1..needed.x2 {
                     for y in needed.y1..needed.y2 {
                         let cell_x = (x - chunk_rect.x1) as i16;
                         let cell_y = (y - chunk_rect.y1) as i16;
                         data[((x - win.x1) + ((y - win.y1) * win_size.x)) as usize] =
-                            cells.get(cell_x, cell_y);
+                            chunk.cells.get(cell_x, cell_y);
                     }
                 }
             }
@@ -172,213 +343,358 @@ impl World {
         // Time
         (data, time.elapsed())
     }
-    fn get_chunk(&self, x: i64, y: i64) -> Rc<CellChunk> {
-        if x == 0 && y == 0 {
-            return self.root.clone();
+    fn get_chunk(&self, x: i64, y: i64) -> Option<&CellChunk> {
+        self.chunks.get(&(x, y))
+    }
+    fn frame(&self, x: i64, y: i64) -> Frame {
+        // Missing neighbours are empty
+        let row = |dx: i64, dy: i64, row: usize| -> u64 {
+            self.get_chunk(x + dx, y + dy)
+                .map_or(0, |chunk| chunk.cells.rows[row])
         };
-        // Determine how much skew and directional moves
-        let moves_skew: i64;
-        let moves_hor: i64;
-        let moves_ver: i64;
-        let x_abs = x.abs();
-        let y_abs = y.abs();
-        if x_abs > y_abs {
-            moves_skew = y_abs;
-            moves_hor = x_abs - y_abs;
-            moves_ver = 0;
-        } else if x_abs < y_abs {
-            moves_skew = x_abs;
-            moves_hor = 0;
-            moves_ver = y_abs - x_abs;
-        } else {
-            moves_skew = x_abs;
-            moves_ver = 0;
-            moves_hor = 0;
+        let mut frame = [0u128; ROWS + 2];
+        for (i, out) in frame.iter_mut().enumerate() {
+            let y = i as i64 - 1;
+            let chunk_y = y.div_euclid(CHUNK_SIZE as i64);
+            let cell_y = y.rem_euclid(CHUNK_SIZE as i64) as usize;
+            let left = row(-1, chunk_y, cell_y) >> (CHUNK_SIZE - 1) & 1;
+            let right = row(1, chunk_y, cell_y) & 1;
+            *out = (row(0, chunk_y, cell_y) as u128) << 1
+                | left as u128
+                | (right as u128) << (CHUNK_SIZE + 1);
+        }
+        frame
+    }
+    pub fn size(&self) -> Vec4<i64> {
+        self.size
+    }
+    fn reserve_chunk(&mut self, chunk_x: i64, chunk_y: i64){
+        // Grow world rectangle over the chunk
+        let chunk_size = CHUNK_SIZE as i64;
+        self.size.x1 = self.size.x1.min(chunk_x * chunk_size);
+        self.size.y1 = self.size.y1.min(chunk_y * chunk_size);
+        self.size.x2 = self.size.x2.max((chunk_x + 1) * chunk_size);
+        self.size.y2 = self.size.y2.max((chunk_y + 1) * chunk_size);
+    }
+
+    pub fn get_cell(&self, x: i64, y: i64) -> u8 {
+        let chunk = self.get_chunk(x.div_euclid(CHUNK_SIZE as i64), y.div_euclid(CHUNK_SIZE as i64));
+        chunk.map_or(0, |chunk| {
+            chunk.cells.get(x.rem_euclid(CHUNK_SIZE as i64) as i16, y.rem_euclid(CHUNK_SIZE as i64) as i16)
+        })
+    }
+    pub fn set_cell(&mut self, x: i64, y: i64, state: bool){
+        self.set_state(x, y, state as u8);
+    }
+    pub fn set_state(&mut self, x: i64, y: i64, state: u8){
+        if self.history.enabled {
+            let old = self.get_cell(x, y);
+            if old != state {
+                self.history.pending.push((x, y, old, state));
+                self.history.redo.clear();
+            }
         }
-        // Directons to index
-        let x_norm = x.signum();
-        let y_norm = y.signum();
-        let skew_dir = dir2index(x_norm, y_norm);
-        let hor_dir = dir2index(x_norm, 0);
-        let ver_dir = dir2index(0, y_norm);
-        // Travel
-        let mut chunk = self.root.clone();
-        let mut last_chunk = chunk.clone();
-        let mut last_move = skew_dir;
-        // Skew
-        for _ in 0..moves_skew {
-            last_chunk = chunk.clone();
-            last_move = skew_dir;
-            chunk = chunk.chunk_to(x_norm, y_norm);
+        self.put_state(x, y, state);
+    }
+    // Set cell without recording it
+    fn put_state(&mut self, x: i64, y: i64, state: u8){
+        // Locate chunk
+        let chunk_x = x.div_euclid(CHUNK_SIZE as i64);
+        let chunk_y = y.div_euclid(CHUNK_SIZE as i64);
+        let cell_x = x.rem_euclid(CHUNK_SIZE as i64) as usize;
+        let cell_y = y.rem_euclid(CHUNK_SIZE as i64) as usize;
+        let pos = (chunk_x, chunk_y);
+        if state == 0 {
+            // Nothing to erase in missing chunks, emptied chunks stop
+            // being stepped
+            if let Some(chunk) = self.chunks.get_mut(&pos) {
+                chunk.cells.set(cell_x, cell_y, 0);
+                if chunk.cells.is_empty() {
+                    self.alive_chunks.remove(&pos);
+                }
+            }
+            return;
         }
-        // Horizontal
-        for _ in 0..moves_hor {
-            last_move = hor_dir;
-            last_chunk = chunk.clone();
-            chunk = chunk.chunk_to(x_norm, 0);
+        // Cell out of world borders
+        self.reserve_chunk(chunk_x, chunk_y);
+        // Set chunk
+        let chunk = self.chunks.entry(pos).or_default();
+        chunk.cells.set(
+            cell_x, 
+            cell_y, 
+            state
+        );
+        self.alive_chunks.insert(pos);
+    }
+    // Next generation of one chunk, None when it is and stays empty
+    fn step_chunk(&self, pos: (i64, i64), counts: Option<(u16, u16)>) -> Option<Cells> {
+        let frame = self.frame(pos.0, pos.1);
+        let empty = Cells::new();
+        let cells = self.get_chunk(pos.0, pos.1).map_or(&empty, |chunk| &chunk.cells);
+        if frame.iter().all(|row| *row == 0) && !cells.has_dying() {
+            return None;
         }
-        // Vertical
-        for _ in 0..moves_ver {
-            last_move = ver_dir;
-            last_chunk = chunk.clone();
-            chunk = chunk.chunk_to(0, y_norm);
+        Some(match counts {
+            Some((birth, survival)) => step_packed(birth, survival, &frame),
+            None => step_cells(&self.rule, &frame, cells),
+        })
+    }
+    pub fn life_step(&mut self){
+        if self.history.enabled {
+            self.commit_edit();
+            let snapshot = match self.history.needs_snapshot() {
+                true => Some(self.snapshot()),
+                false => None,
+            };
+            self.history.push(Change::Step(snapshot));
+            self.history.redo.clear();
         }
-        // Point from last chunk
-        let chunk = last_chunk.border.borrow()[last_move]
-            .as_ref()
-            .unwrap()
-            .clone();
-        chunk
-    }
-    pub fn resize(&mut self, x: i64, y: i64) {
-        // ---------X-----------
-        if x != 0 {
-            for _ in 0..x.abs(){
-                // Get first chunk
-                let chunk_x = if x.signum() == 1 {
-                    (self.size.x2 / CHUNK_SIZE as i64) - 1
-                } else {
-                    self.size.x1 / CHUNK_SIZE as i64
-                };
-                let chunk_y = self.size.y1 / CHUNK_SIZE as i64;
-                let chunk = self.get_chunk(chunk_x, chunk_y);
-
-                // Create first new chunk
-                let mut new_last = Rc::new(CellChunk::new());
-                let mut border_last = chunk;
-                self.connect(&border_last, &new_last, x.signum(), 0);
-                // Iterate downards if necessary 
-                let chunk_count = (self.size.y2 - self.size.y1) / CHUNK_SIZE as i64;
-                for _ in 0..chunk_count-1{
-                    let new = Rc::new(CellChunk::new());
-                    let border = border_last.chunk_to(0, 1);
-
-                    self.connect(&new, &new_last, 0, -1);
-                    self.connect(&new, &border_last, -x.signum(), -1);
-                    self.connect(&new, &border, -x.signum(), 0);
-                    self.connect(&border, &new_last, x.signum(), -1);
-
-                    new_last = new;
-                    border_last = border;
+        self.step();
+    }
+    fn step(&mut self){
+        // Packed stepping only knows neighbour counts and two states
+        let counts = match self.rule.states() {
+            2 => self.rule.counts(),
+            _ => None,
+        };
+        // Living chunks and their neighbours can change
+        let mut candidates = HashSet::<(i64, i64)>::new();
+        for pos in self.alive_chunks.iter(){
+            candidates.insert(*pos);
+            for i in 0..8{
+                let dir = index2dir(i);
+                candidates.insert((pos.0 + dir.0 as i64, pos.1 + dir.1 as i64));
+            }
+        }
+        let candidates: Vec<(i64, i64)> = candidates.into_iter().collect();
+        // Calculate next generation from the previous one, chunks only read
+        // the world so they can be split between threads
+        let workers = self.threads.min(candidates.len() / CHUNKS_PER_THREAD).max(1);
+        let world = &*self;
+        let next: Vec<((i64, i64), Option<Cells>)> = if workers == 1 {
+            candidates.iter().map(|pos| (*pos, world.step_chunk(*pos, counts))).collect()
+        } else {
+            let part = candidates.len().div_ceil(workers);
+            thread::scope(|scope| {
+                let handles: Vec<_> = candidates
+                    .chunks(part)
+                    .map(|part| {
+                        scope.spawn(move || {
+                            part.iter()
+                                .map(|pos| (*pos, world.step_chunk(*pos, counts)))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+            })
+        };
+        // Write new generation
+        let mut step = StepStats::default();
+        for (pos, cells) in next{
+            let cells = match cells {
+                Some(cells) => cells,
+                None => {
+                    self.alive_chunks.remove(&pos);
+                    continue;
                 }
-
-                // Increase world size
-                if x.signum() == 1 {
-                    self.size.x2 += CHUNK_SIZE as i64;
-                } else {
-                    self.size.x1 -= CHUNK_SIZE as i64;
+            };
+            if let Some(chunk) = self.chunks.get(&pos) {
+                step.births += cells.count_missing(&chunk.cells);
+                step.deaths += chunk.cells.count_missing(&cells);
+            } else {
+                step.births += cells.population();
+            }
+            if cells.is_empty() {
+                self.alive_chunks.remove(&pos);
+                if let Some(chunk) = self.chunks.get_mut(&pos) {
+                    chunk.cells = cells;
                 }
+            } else {
+                self.chunks.entry(pos).or_default().cells = cells;
+                self.alive_chunks.insert(pos);
             }
         }
-        // ---------Y-----------
-        if y != 0 {
-            for _ in 0..y.abs(){
-                // Get first chunk
-                let chunk_y = if y.signum() == 1 {
-                    (self.size.y2 / CHUNK_SIZE as i64) - 1
-                } else {
-                    self.size.y1 / CHUNK_SIZE as i64
-                };
-                let chunk_x = self.size.x1 / CHUNK_SIZE as i64;
-                let chunk = self.get_chunk(chunk_x, chunk_y);
-
-                // Create first new chunk
-                let mut new_last = Rc::new(CellChunk::new());
-                let mut border_last = chunk;
-                self.connect(&border_last, &new_last, 0, y.signum());
-                // Iterate right if necessary 
-                let chunk_count = (self.size.x2 - self.size.x1) / CHUNK_SIZE as i64;
-                for _ in 0..chunk_count-1{
-                    let new = Rc::new(CellChunk::new());
-                    let border = border_last.chunk_to(1, 0);
-
-                    self.connect(&new, &new_last, -1, 0);
-                    self.connect(&new, &border_last, -1, -y.signum());
-                    self.connect(&new, &border, 0, -y.signum());
-                    self.connect(&border, &new_last, -1, y.signum());
-
-                    new_last = new;
-                    border_last = border;
+        self.collect();
+        self.generation += 1;
+        self.stats.record(self.generation, self.chunk_population(), step);
+    }
+    // Drop chunks that stayed empty for too long and shrink the world
+    // rectangle to the chunks that are still alive
+    fn collect(&mut self) {
+        let alive = &self.alive_chunks;
+        let gc_idle = self.gc_idle;
+        self.chunks.retain(|pos, chunk| {
+            if alive.contains(pos) {
+                chunk.idle = 0;
+                return true;
+            }
+            chunk.idle += 1;
+            chunk.idle <= gc_idle
+        });
+        if self.chunks.capacity() > 4 * self.chunks.len().max(16) {
+            self.chunks.shrink_to_fit();
+        }
+        if self.alive_chunks.capacity() > 4 * self.alive_chunks.len().max(16) {
+            self.alive_chunks.shrink_to_fit();
+        }
+        let chunk_size = CHUNK_SIZE as i64;
+        let mut alive = self.alive_chunks.iter();
+        self.size = match alive.next() {
+            Some(first) => {
+                let mut size = Vec4 { x1: first.0, y1: first.1, x2: first.0, y2: first.1 };
+                for pos in alive {
+                    size.x1 = size.x1.min(pos.0);
+                    size.y1 = size.y1.min(pos.1);
+                    size.x2 = size.x2.max(pos.0);
+                    size.y2 = size.y2.max(pos.1);
                 }
-
-                // Increase world size
-                if y.signum() == 1 {
-                    self.size.y2 += CHUNK_SIZE as i64;
-                } else {
-                    self.size.y1 -= CHUNK_SIZE as i64;
+                Vec4 {
+                    x1: size.x1 * chunk_size,
+                    y1: size.y1 * chunk_size,
+                    x2: (size.x2 + 1) * chunk_size,
+                    y2: (size.y2 + 1) * chunk_size,
                 }
             }
+            None => EMPTY_SIZE,
+        };
+    }
+    // Undo and redo, off until enabled. Cell edits are grouped into one
+    // change until a step, an undo or commit_edit, every generation is a
+    // change of its own
+    pub fn set_history(&mut self, enabled: bool) {
+        self.history.enabled = enabled;
+        self.clear_history();
+    }
+    pub fn clear_history(&mut self) {
+        let enabled = self.history.enabled;
+        self.history = History::new();
+        self.history.enabled = enabled;
+    }
+    pub fn commit_edit(&mut self) {
+        if !self.history.pending.is_empty() {
+            let edit = std::mem::take(&mut self.history.pending);
+            self.history.push(Change::Edit(edit));
         }
     }
-    fn connect(&self, chunk1: &Rc<CellChunk>, chunk2: &Rc<CellChunk>, x: i64, y: i64){
-        chunk1.border.borrow_mut()[dir2index(x, y)] = Some(chunk2.clone());
-        chunk2.border.borrow_mut()[dir2index(-x, -y)] = Some(chunk1.clone());
+    pub fn can_undo(&self) -> bool {
+        !self.history.pending.is_empty() || !self.history.undo.is_empty()
     }
-    pub fn size(&self) -> Vec4<i64> {
-        self.size
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
     }
-
-    pub fn set_cell(&mut self, x: i64, y: i64, state: bool){
-        // Cell out of world borders
-        let mut expand_x = 0;
-        let mut expand_y = 0;
-        if x >= self.size.x2 {
-            expand_x = ((x - self.size.x2 + 2) as f64 / CHUNK_SIZE as f64).ceil() as i64;
+    pub fn undo(&mut self) -> bool {
+        self.commit_edit();
+        let change = match self.history.undo.pop_back() {
+            Some(change) => change,
+            None => return false,
+        };
+        match &change {
+            Change::Edit(edit) => {
+                for (x, y, old, _) in edit.iter().rev() {
+                    self.put_state(*x, *y, *old);
+                }
+            }
+            Change::Step(Some(snapshot)) => self.restore(snapshot.generation, &snapshot.cells),
+            Change::Step(None) => {
+                // Replay from the last snapshot up to the generation before
+                // Nothing to replay from, the step stays where it is
+                let (generation, cells, steps) = match self.history.last_snapshot() {
+                    Some((snapshot, steps)) => (snapshot.generation, snapshot.cells.clone(), steps),
+                    None => {
+                        self.history.undo.push_back(change);
+                        return false;
+                    }
+                };
+                self.restore(generation, &cells);
+                for _ in 0..steps {
+                    self.step();
+                }
+            }
+            Change::Rule(old, _) => self.rule = *old,
+            Change::Replace { old, old_rule, .. } => {
+                self.restore(old.generation, &old.cells);
+                self.rule = *old_rule;
+            }
         }
-        if x <= self.size.x1 {
-            expand_x = ((x - self.size.x1 - 2) as f64 / CHUNK_SIZE as f64).floor() as i64;
+        self.history.redo.push(change);
+        true
+    }
+    pub fn redo(&mut self) -> bool {
+        self.commit_edit();
+        let change = match self.history.redo.pop() {
+            Some(change) => change,
+            None => return false,
+        };
+        match &change {
+            Change::Edit(edit) => {
+                for (x, y, _, new) in edit.iter() {
+                    self.put_state(*x, *y, *new);
+                }
+            }
+            Change::Step(_) => self.step(),
+            Change::Rule(_, new) => self.rule = *new,
+            Change::Replace { new, new_rule, .. } => {
+                self.restore(new.generation, &new.cells);
+                self.rule = *new_rule;
+            }
         }
-        if y >= self.size.y2 {
-            expand_y = ((y - self.size.y2 + 2) as f64 / CHUNK_SIZE as f64).ceil() as i64;
+        self.history.push(change);
+        true
+    }
+    // Cells, rule and generation from elsewhere as a single change
+    pub fn replace(&mut self, generation: u64, rule: Rule, cells: &[(i64, i64, u8)]) {
+        self.commit_edit();
+        let old = self.history.enabled.then(|| (self.snapshot(), self.rule));
+        self.restore(generation, cells);
+        self.rule = rule;
+        if let Some((old, old_rule)) = old {
+            let new = self.snapshot();
+            self.history.push(Change::Replace { old, old_rule, new, new_rule: rule });
+            self.history.redo.clear();
         }
-        if y <= self.size.y1 {
-            expand_y = ((y - self.size.y1 - 2) as f64 / CHUNK_SIZE as f64).floor() as i64;
+    }
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            generation: self.generation,
+            cells: self.live_cells(),
         }
-        if expand_x != 0 || expand_y != 0{
-            self.resize(expand_x, expand_y);
+    }
+    fn restore(&mut self, generation: u64, cells: &[(i64, i64, u8)]) {
+        self.chunks.clear();
+        self.alive_chunks.clear();
+        self.size = EMPTY_SIZE;
+        for (x, y, state) in cells {
+            self.put_state(*x, *y, *state);
         }
-        // Locate chunk
-        let chunk_x = ((x) as f64 / CHUNK_SIZE as f64).floor() as i64;
-        let chunk_y = ((y) as f64 / CHUNK_SIZE as f64).floor() as i64;
-        let cell_x = (x - (chunk_x * CHUNK_SIZE as i64)) as usize;
-        let cell_y = (y - (chunk_y * CHUNK_SIZE as i64)) as usize;
-        // Set chunk
-        let chunk =  self.get_chunk(chunk_x, chunk_y);
-        chunk.cells.borrow_mut().set(
-            cell_x, 
-            cell_y, 
-            state
-        );
-        chunk.alive.borrow_mut().insert((cell_x as i16, cell_y as i16), ());
-        self.alive_chunks.insert((chunk_x, chunk_y), chunk);
+        self.generation = generation;
     }
-    pub fn life_step(&mut self){
-        // Check live cells to make surrounding cells
-        let mut born_chunks = Vec::<((i64, i64), Rc<CellChunk>)>::new();
-        let mut alive_border = Vec4::<i64>::new();
-
-        for chunk in self.alive_chunks.iter(){
-            for cell in chunk.1.alive.borrow().iter(){
-                for i in 0..8{
-                    let dir = index2dir(i);
-                    let pos = (cell.0.0 + dir.0, cell.0.1 + dir.1);
-
-                    // If there isnt alive cell, mark it to check
-                    if !chunk.1.get_cell(pos.0, pos.1){
-                        if let Some(born_chunk) = chunk.1.set_check(pos.0, pos.1){
-                            // If there is newborn chunk
-                            let chunk_x = born_chunk.0.0 + chunk.0.0;
-                            let chunk_y = born_chunk.0.1 + chunk.0.1;
-                            if chunk_x > alive_border.x2 {alive_border.x2 = chunk_x};
-                            if chunk_x < alive_border.x1 {alive_border.x1 = chunk_x};
-                            if chunk_y > alive_border.y2 {alive_border.y2 = chunk_y};
-                            if chunk_y < alive_border.y1 {alive_border.y2 = chunk_y};
-                            born_chunks.push(((chunk_x, chunk_y), born_chunk.1));
-                        }
-                    }
+    // Replace a rectangle with a random soup, the seed regenerates it
+    pub fn fill_random(&mut self, rect: Vec4<i64>, density: f64, seed: u64) {
+        place_soup(self, rect, density, seed);
+    }
+    pub fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut cells = Vec::new();
+        for pos in self.alive_chunks.iter() {
+            let chunk_cells = &self.chunks[pos].cells;
+            for y in 0..ROWS {
+                let mut row = chunk_cells.rows[y] | chunk_cells.dying[y];
+                while row != 0 {
+                    let x = row.trailing_zeros() as i16;
+                    row &= row - 1;
+                    cells.push((
+                        pos.0 * CHUNK_SIZE as i64 + x as i64,
+                        pos.1 * CHUNK_SIZE as i64 + y as i64,
+                        chunk_cells.get(x, y as i16),
+                    ));
                 }
             }
         }
+        cells
+    }
+}
+impl Default for World {
+    fn default() -> Self {
+        World::new()
     }
-    
 }
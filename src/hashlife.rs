@@ -0,0 +1,465 @@
+use std::{collections::HashMap, mem, time::{Duration, Instant}};
+
+use crate::engine::drop_states;
+use crate::math::*;
+use crate::rule::Rule;
+
+pub(crate) type NodeId = u32;
+
+// Nodes are rebuilt from the root when the arena grows past this
+const GC_NODES: usize = 1 << 22;
+const MIN_LEVEL: u8 = 3;
+// Largest root whose side still fits in i64 coordinates
+const MAX_LEVEL: u8 = 62;
+// Heap used by a node and its lookup entry
+pub(crate) const NODE_BYTES: usize = mem::size_of::<Node>() + mem::size_of::<((u8, [NodeId; 4]), NodeId)>();
+
+struct Node {
+    level: u8,
+    // nw, ne, sw, se or cell state in first field for leaves
+    children: [NodeId; 4],
+    population: u64,
+}
+
+// Quadtree where every distinct node exists only once, so a result
+// computed for one node is shared by all of its copies
+pub struct HashLife {
+    nodes: Vec<Node>,
+    lookup: HashMap<(u8, [NodeId; 4]), NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+    // Top left corner of root
+    origin: (i64, i64),
+    rule: Rule,
+    generation: u64,
+}
+impl HashLife {
+    pub fn new() -> Self {
+        let mut life = HashLife {
+            nodes: Vec::new(),
+            lookup: HashMap::new(),
+            results: HashMap::new(),
+            empty: Vec::new(),
+            root: 0,
+            origin: (0, 0),
+            rule: Rule::life(),
+            generation: 0,
+        };
+        life.root = life.empty(MIN_LEVEL);
+        let half = 1 << (MIN_LEVEL - 1);
+        life.origin = (-half, -half);
+        life
+    }
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+    pub fn set_rule(&mut self, rule: Rule) {
+        drop_states(self, rule);
+        self.rule = rule;
+        self.results.clear();
+    }
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+    pub fn size(&self) -> Vec4<i64> {
+        let side = 1 << self.nodes[self.root as usize].level;
+        Vec4 {
+            x1: self.origin.0,
+            y1: self.origin.1,
+            x2: self.origin.0 + side,
+            y2: self.origin.1 + side,
+        }
+    }
+
+    pub(crate) fn leaf(&mut self, state: u8) -> NodeId {
+        self.insert(0, [state as NodeId, 0, 0, 0], (state != 0) as u64)
+    }
+    pub(crate) fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let level = self.nodes[nw as usize].level + 1;
+        let population = [nw, ne, sw, se]
+            .iter()
+            .map(|n| self.nodes[*n as usize].population)
+            .sum();
+        self.insert(level, [nw, ne, sw, se], population)
+    }
+    fn insert(&mut self, level: u8, children: [NodeId; 4], population: u64) -> NodeId {
+        if let Some(id) = self.lookup.get(&(level, children)) {
+            return *id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node { level, children, population });
+        self.lookup.insert((level, children), id);
+        id
+    }
+    pub(crate) fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let node = match self.empty.last() {
+                Some(e) => self.join(*e, *e, *e, *e),
+                None => self.leaf(0),
+            };
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+    // Level, children and population of a node
+    pub(crate) fn node(&self, node: NodeId) -> (u8, [NodeId; 4], u64) {
+        let n = &self.nodes[node as usize];
+        (n.level, n.children, n.population)
+    }
+    pub(crate) fn root(&self) -> NodeId {
+        self.root
+    }
+    // Replace the whole pattern, root is centred on 0, 0
+    pub(crate) fn set_root(&mut self, root: NodeId) {
+        let half = 1 << (self.nodes[root as usize].level.max(1) - 1);
+        self.root = root;
+        self.origin = (-half, -half);
+        while self.nodes[self.root as usize].level < MIN_LEVEL {
+            self.expand();
+        }
+    }
+    // Root kept with its top left corner as it is
+    pub(crate) fn set_root_at(&mut self, root: NodeId, origin: (i64, i64)) {
+        self.root = root;
+        self.origin = origin;
+    }
+    // Copy of a node of another quadtree, sharing nodes this one has
+    pub(crate) fn import(&mut self, from: &HashLife, node: NodeId) -> NodeId {
+        self.copy_node(from, node, &mut HashMap::new())
+    }
+    fn children(&self, node: NodeId) -> [NodeId; 4] {
+        self.nodes[node as usize].children
+    }
+    fn centre(&mut self, node: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(node);
+        self.join(
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        )
+    }
+    // Pattern only lives in the centre of root
+    fn centred(&self, node: NodeId) -> bool {
+        let [nw, ne, sw, se] = self.children(node);
+        let outer = [(nw, [0, 1, 2]), (ne, [0, 1, 3]), (sw, [0, 2, 3]), (se, [1, 2, 3])];
+        outer.iter().all(|(child, quads)| {
+            let children = self.children(*child);
+            quads.iter().all(|q| self.nodes[children[*q] as usize].population == 0)
+        })
+    }
+    // False when root is as large as coordinates allow
+    fn expand(&mut self) -> bool {
+        let level = self.nodes[self.root as usize].level;
+        if level >= MAX_LEVEL {
+            return false;
+        }
+        let e = self.empty(level - 1);
+        let [nw, ne, sw, se] = self.children(self.root);
+        let nw = self.join(e, e, e, nw);
+        let ne = self.join(e, e, ne, e);
+        let sw = self.join(e, sw, e, e);
+        let se = self.join(se, e, e, e);
+        self.root = self.join(nw, ne, sw, se);
+        let half = 1 << (level - 1);
+        self.origin = (self.origin.0 - half, self.origin.1 - half);
+        true
+    }
+    fn shrink(&mut self) {
+        while self.nodes[self.root as usize].level > MIN_LEVEL && self.centred(self.root) {
+            let level = self.nodes[self.root as usize].level;
+            self.root = self.centre(self.root);
+            let quarter = 1 << (level - 2);
+            self.origin = (self.origin.0 + quarter, self.origin.1 + quarter);
+        }
+    }
+
+    pub fn set_cell(&mut self, x: i64, y: i64, state: bool) {
+        self.set_state(x, y, state as u8);
+    }
+    pub fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        // Grow until cell is inside of root, cells out of reach are dropped
+        loop {
+            let size = self.size();
+            if x >= size.x1 && x < size.x2 && y >= size.y1 && y < size.y2 {
+                break;
+            }
+            if !self.expand() {
+                return;
+            }
+        }
+        self.root = self.set_node(self.root, x - self.origin.0, y - self.origin.1, state);
+    }
+    fn set_node(&mut self, node: NodeId, x: i64, y: i64, state: u8) -> NodeId {
+        let level = self.nodes[node as usize].level;
+        if level == 0 {
+            return self.leaf(state);
+        }
+        let half = 1 << (level - 1);
+        let quad = (x >= half) as usize + 2 * (y >= half) as usize;
+        let mut children = self.children(node);
+        children[quad] = self.set_node(children[quad], x % half, y % half, state);
+        self.join(children[0], children[1], children[2], children[3])
+    }
+    pub fn get_cell(&self, x: i64, y: i64) -> u8 {
+        let size = self.size();
+        if x < size.x1 || x >= size.x2 || y < size.y1 || y >= size.y2 {
+            return 0;
+        }
+        let mut node = self.root;
+        let (mut x, mut y) = (x - self.origin.0, y - self.origin.1);
+        while self.nodes[node as usize].level > 0 {
+            let half = 1 << (self.nodes[node as usize].level - 1);
+            node = self.children(node)[(x >= half) as usize + 2 * (y >= half) as usize];
+            x %= half;
+            y %= half;
+        }
+        self.children(node)[0] as u8
+    }
+
+    pub fn get_world(&self, win: Vec4<i64>) -> (Vec<u8>, Duration) {
+        // Time
+        let time = Instant::now();
+        // Data
+        let win_size = win.size();
+        let mut data = vec![0; (win_size.x * win_size.y) as usize];
+        self.fill(self.root, self.origin.0, self.origin.1, &win, &mut data);
+        (data, time.elapsed())
+    }
+    fn fill(&self, node: NodeId, x: i64, y: i64, win: &Vec4<i64>, data: &mut Vec<u8>) {
+        let n = &self.nodes[node as usize];
+        if n.population == 0 {
+            return;
+        }
+        let side = 1 << n.level;
+        let rect = Vec4 { x1: x, y1: y, x2: x + side, y2: y + side };
+        if win.intersect(&rect).is_none() {
+            return;
+        }
+        if n.level == 0 {
+            data[((x - win.x1) + (y - win.y1) * win.size().x) as usize] = n.children[0] as u8;
+            return;
+        }
+        let half = side / 2;
+        for (i, child) in n.children.iter().enumerate() {
+            let child_x = x + half * (i % 2) as i64;
+            let child_y = y + half * (i / 2) as i64;
+            self.fill(*child, child_x, child_y, win, data);
+        }
+    }
+
+    // Advance a 4x4 node one generation, returns its 2x2 centre
+    fn base_step(&mut self, node: NodeId) -> NodeId {
+        let mut grid = [[0u8; 4]; 4];
+        for (i, child) in self.children(node).iter().enumerate() {
+            for (j, leaf) in self.children(*child).iter().enumerate() {
+                let x = (i % 2) * 2 + j % 2;
+                let y = (i / 2) * 2 + j / 2;
+                grid[y][x] = self.children(*leaf)[0] as u8;
+            }
+        }
+        let mut next = [0; 4];
+        for (i, state) in next.iter_mut().enumerate() {
+            let x = 1 + i % 2;
+            let y = 1 + i / 2;
+            let mut mask = 0;
+            for n in 0..8 {
+                let dir = index2dir(n);
+                let neighbour = grid[(y as i16 + dir.1) as usize][(x as i16 + dir.0) as usize];
+                if neighbour == 1 {
+                    mask |= 1 << n;
+                }
+            }
+            *state = self.leaf(self.rule.next(grid[y][x], mask));
+        }
+        self.join(next[0], next[1], next[2], next[3])
+    }
+    // Centre of node advanced 2^j generations, j is at most level - 2
+    fn successor(&mut self, node: NodeId, j: u8) -> NodeId {
+        let level = self.nodes[node as usize].level;
+        if self.nodes[node as usize].population == 0 {
+            return self.empty(level - 1);
+        }
+        let j = j.min(level - 2);
+        if let Some(result) = self.results.get(&(node, j)) {
+            return *result;
+        }
+        let result = if level == 2 {
+            self.base_step(node)
+        } else {
+            // Nine overlapping subnodes
+            let [nw, ne, sw, se] = self.children(node);
+            let [_, nw_ne, nw_sw, nw_se] = self.children(nw);
+            let [ne_nw, _, ne_sw, ne_se] = self.children(ne);
+            let [sw_nw, sw_ne, _, sw_se] = self.children(sw);
+            let [se_nw, se_ne, se_sw, _] = self.children(se);
+            let sub = [
+                nw,
+                self.join(nw_ne, ne_nw, nw_se, ne_sw),
+                ne,
+                self.join(nw_sw, nw_se, sw_nw, sw_ne),
+                self.join(nw_se, ne_sw, sw_ne, se_nw),
+                self.join(ne_sw, ne_se, se_nw, se_ne),
+                sw,
+                self.join(sw_ne, se_nw, sw_se, se_sw),
+                se,
+            ];
+            let mut c = [0; 9];
+            for (i, node) in sub.iter().enumerate() {
+                c[i] = self.successor(*node, j);
+            }
+            let groups = [[0, 1, 3, 4], [1, 2, 4, 5], [3, 4, 6, 7], [4, 5, 7, 8]];
+            let mut quads = [0; 4];
+            for (quad, group) in quads.iter_mut().zip(groups) {
+                *quad = if j < level - 2 {
+                    // Only half of the time, take centres without advancing
+                    let a = self.children(c[group[0]])[3];
+                    let b = self.children(c[group[1]])[2];
+                    let c_ = self.children(c[group[2]])[1];
+                    let d = self.children(c[group[3]])[0];
+                    self.join(a, b, c_, d)
+                } else {
+                    let joined = self.join(c[group[0]], c[group[1]], c[group[2]], c[group[3]]);
+                    self.successor(joined, j)
+                };
+            }
+            self.join(quads[0], quads[1], quads[2], quads[3])
+        };
+        self.results.insert((node, j), result);
+        result
+    }
+
+    pub fn life_step(&mut self) {
+        self.step_pow2(0);
+    }
+    // False without stepping when the padded root or the generation
+    // counter would get too large
+    pub fn step_pow2(&mut self, j: u8) -> bool {
+        if j > MAX_LEVEL - 3 || self.generation.checked_add(1 << j).is_none() {
+            return false;
+        }
+        if self.nodes.len() > GC_NODES {
+            self.collect();
+        }
+        // Pad so the pattern can't leave the centre in 2^j generations
+        while self.nodes[self.root as usize].level < j + 2 || !self.centred(self.root) {
+            if !self.expand() {
+                return false;
+            }
+        }
+        if !self.expand() {
+            return false;
+        }
+        let level = self.nodes[self.root as usize].level;
+        self.root = self.successor(self.root, j);
+        let quarter = 1 << (level - 2);
+        self.origin = (self.origin.0 + quarter, self.origin.1 + quarter);
+        self.shrink();
+        self.generation += 1 << j;
+        true
+    }
+
+    // Rebuild arena with only nodes reachable from root
+    fn collect(&mut self) {
+        let mut fresh = HashLife::new();
+        fresh.rule = self.rule;
+        let mut remap = HashMap::new();
+        fresh.root = fresh.copy_node(self, self.root, &mut remap);
+        fresh.origin = self.origin;
+        fresh.generation = self.generation;
+        *self = fresh;
+    }
+    fn copy_node(&mut self, from: &HashLife, node: NodeId, remap: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if let Some(id) = remap.get(&node) {
+            return *id;
+        }
+        let n = &from.nodes[node as usize];
+        let id = if n.level == 0 {
+            self.leaf(n.children[0] as u8)
+        } else {
+            let mut children = [0; 4];
+            for (i, child) in n.children.iter().enumerate() {
+                children[i] = self.copy_node(from, *child, remap);
+            }
+            self.join(children[0], children[1], children[2], children[3])
+        };
+        remap.insert(node, id);
+        id
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+    // Approximate heap used by nodes and caches
+    pub fn memory(&self) -> usize {
+        self.nodes.capacity() * mem::size_of::<Node>()
+            + self.lookup.capacity() * mem::size_of::<((u8, [NodeId; 4]), NodeId)>()
+            + self.results.capacity() * mem::size_of::<((NodeId, u8), NodeId)>()
+            + self.empty.capacity() * mem::size_of::<NodeId>()
+    }
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+    pub fn bounding_box(&self) -> Option<Vec4<i64>> {
+        let mut rect = None;
+        self.bounds(self.root, self.origin.0, self.origin.1, &mut rect);
+        rect
+    }
+    fn bounds(&self, node: NodeId, x: i64, y: i64, rect: &mut Option<Vec4<i64>>) {
+        let n = &self.nodes[node as usize];
+        if n.population == 0 {
+            return;
+        }
+        let side = 1 << n.level;
+        // Skip nodes that can't make the box any bigger
+        if let Some(r) = rect {
+            if x >= r.x1 && y >= r.y1 && x + side <= r.x2 && y + side <= r.y2 {
+                return;
+            }
+        }
+        if n.level == 0 {
+            *rect = Some(match rect {
+                Some(r) => Vec4 {
+                    x1: r.x1.min(x),
+                    y1: r.y1.min(y),
+                    x2: r.x2.max(x + 1),
+                    y2: r.y2.max(y + 1),
+                },
+                None => Vec4 { x1: x, y1: y, x2: x + 1, y2: y + 1 },
+            });
+            return;
+        }
+        let half = side / 2;
+        for (i, child) in n.children.iter().enumerate() {
+            self.bounds(*child, x + half * (i % 2) as i64, y + half * (i / 2) as i64, rect);
+        }
+    }
+    pub fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut cells = Vec::new();
+        self.collect_cells(self.root, self.origin.0, self.origin.1, &mut cells);
+        cells
+    }
+    fn collect_cells(&self, node: NodeId, x: i64, y: i64, cells: &mut Vec<(i64, i64, u8)>) {
+        let n = &self.nodes[node as usize];
+        if n.population == 0 {
+            return;
+        }
+        if n.level == 0 {
+            cells.push((x, y, n.children[0] as u8));
+            return;
+        }
+        let half = 1 << (n.level - 1);
+        for (i, child) in n.children.iter().enumerate() {
+            self.collect_cells(*child, x + half * (i % 2) as i64, y + half * (i / 2) as i64, cells);
+        }
+    }
+}
+impl Default for HashLife {
+    fn default() -> Self {
+        HashLife::new()
+    }
+}
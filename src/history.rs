@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+
+use crate::rule::Rule;
+
+// Stepping back through a run of generations replays at most this many
+// steps from the last snapshot
+pub(crate) const SNAPSHOT_EVERY: usize = 64;
+// Oldest changes are forgotten past this many changes or snapshot cells
+pub(crate) const MAX_CHANGES: usize = 4096;
+pub(crate) const MAX_SNAPSHOT_CELLS: usize = 1 << 22;
+
+// Every non zero cell at some generation
+pub(crate) struct Snapshot {
+    pub(crate) generation: u64,
+    pub(crate) cells: Vec<(i64, i64, u8)>,
+}
+
+pub(crate) enum Change {
+    // Cells set by the user as x, y, old state and new state
+    Edit(Vec<(i64, i64, u8, u8)>),
+    // One generation. The world before it is kept at the start of a run
+    // of steps and every SNAPSHOT_EVERY steps after that
+    Step(Option<Snapshot>),
+    // Old and new rule, steps after it replay under the new one
+    Rule(Rule, Rule),
+    // Whole world swapped for another generation, rule included
+    Replace { old: Snapshot, old_rule: Rule, new: Snapshot, new_rule: Rule },
+}
+
+pub(crate) struct History {
+    pub(crate) enabled: bool,
+    pub(crate) undo: VecDeque<Change>,
+    pub(crate) redo: Vec<Change>,
+    // Edits not yet closed into a change
+    pub(crate) pending: Vec<(i64, i64, u8, u8)>,
+}
+impl History {
+    pub(crate) fn new() -> Self {
+        History {
+            enabled: false,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+    pub(crate) fn push(&mut self, change: Change) {
+        self.undo.push_back(change);
+        while self.undo.len() > 1 && (self.undo.len() > MAX_CHANGES || self.snapshot_cells() > MAX_SNAPSHOT_CELLS) {
+            self.undo.pop_front();
+            // Steps can't be undone without the snapshot they start from
+            while let Some(Change::Step(None)) = self.undo.front() {
+                self.undo.pop_front();
+            }
+        }
+    }
+    fn snapshot_cells(&self) -> usize {
+        let cells = |change: &Change| match change {
+            Change::Step(Some(snapshot)) => snapshot.cells.len(),
+            Change::Replace { old, new, .. } => old.cells.len() + new.cells.len(),
+            _ => 0,
+        };
+        self.undo.iter().map(cells).sum::<usize>() + self.redo.iter().map(cells).sum::<usize>()
+    }
+    // Whether the next step should keep a snapshot
+    pub(crate) fn needs_snapshot(&self) -> bool {
+        let mut steps = 0;
+        for change in self.undo.iter().rev() {
+            match change {
+                Change::Step(None) => steps += 1,
+                Change::Step(Some(_)) => return steps + 1 >= SNAPSHOT_EVERY,
+                Change::Edit(_) | Change::Rule(..) | Change::Replace { .. } => return true,
+            }
+        }
+        true
+    }
+    // Snapshot and the number of steps after it that lead to the end of
+    // the undo stack
+    pub(crate) fn last_snapshot(&self) -> Option<(&Snapshot, usize)> {
+        for (steps, change) in self.undo.iter().rev().enumerate() {
+            match change {
+                Change::Step(None) => {}
+                Change::Step(Some(snapshot)) => return Some((snapshot, steps + 1)),
+                Change::Edit(_) | Change::Rule(..) | Change::Replace { .. } => return None,
+            }
+        }
+        None
+    }
+}
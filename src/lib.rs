@@ -1,36 +1,145 @@
+mod command;
+mod dense;
+mod engine;
+mod format;
 mod gol;
+mod hashlife;
+mod history;
+mod library;
+mod life;
+mod macrocell;
 mod math;
-use crate::gol::*;
-use crate::math::*;
-use crossterm::cursor::{Hide, MoveDown, MoveTo, Show};
+mod pattern;
+mod plaintext;
+mod rle;
+mod rule;
+mod selection;
+mod session;
+mod soup;
+mod stats;
+mod timeline;
+mod tools;
+pub use crate::command::*;
+pub use crate::dense::*;
+pub use crate::engine::*;
+pub use crate::format::*;
+pub use crate::gol::*;
+pub use crate::hashlife::*;
+pub use crate::library::*;
+pub use crate::life::*;
+pub use crate::macrocell::*;
+pub use crate::math::*;
+pub use crate::pattern::*;
+pub use crate::plaintext::*;
+pub use crate::rle::*;
+pub use crate::rule::*;
+pub use crate::selection::*;
+pub use crate::session::*;
+pub use crate::soup::*;
+pub use crate::stats::*;
+pub use crate::timeline::*;
+pub use crate::tools::*;
+use crossterm::cursor::{Hide, MoveTo, Show};
 use crossterm::event::DisableMouseCapture;
-use crossterm::event::{self, KeyCode, MouseButton, MouseEventKind, MouseEvent};
+use crossterm::event::{self, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind, MouseEvent};
 use crossterm::style::{Color, Print, SetBackgroundColor, SetForegroundColor};
 use crossterm::terminal::SetSize;
 use crossterm::terminal::{
-    self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
     SetTitle,
 };
 use crossterm::Result;
 use crossterm::{execute, queue};
+use std::collections::HashSet;
 use std::io::Write;
 use std::io::{stdout, Stdout};
-use std::time::Duration;
-use std::time::Instant;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Longest time a simulation tick may spend stepping before it gives up
+// on generations it is behind by
+const TICK_BUDGET: Duration = Duration::from_millis(10);
+const MAX_SPEED: u32 = 1 << 16;
+// Largest power of two a single jump step asks for
+const MAX_JUMP_EXP: u32 = 62;
+const MAX_BRUSH: u8 = 32;
+// Generations shown in the population sparkline
+const SPARKLINE_WIDTH: usize = 40;
+// Larger macrocells replace the world with HashLife when opened
+const MAX_TRANSFER_CELLS: u64 = 1 << 16;
+// Generations kept for scrubbing back, fewer when they take more memory
+const TIMELINE_LENGTH: usize = 1000;
+const TIMELINE_BYTES: usize = 64 << 20;
+fn state_color(state: u8, states: u8) -> Color {
+    match state {
+        0 => Color::Black,
+        1 => Color::Cyan,
+        // Decay states fade from yellow to dark red
+        _ => {
+            let t = (states - state.min(states - 1)) as u16 * 255 / (states - 1) as u16;
+            Color::Rgb {
+                r: 128 + (t / 2) as u8,
+                g: t as u8,
+                b: 0,
+            }
+        }
+    }
+}
+
 pub struct App {
     pub run: bool,
     win_info_init: Vec2<u16>,
     win_info: Vec4<i64>,
     pub out: Stdout,
-    world: World,
-    pub mouse_pos: Vec2<u16>
+    world: Box<dyn LifeEngine>,
+    step_exp: u8,
+    pub mouse_pos: Vec2<u16>,
+    // Continuous play, speed in generations per second
+    playing: bool,
+    speed: u32,
+    last_tick: Instant,
+    owed: f64,
+    // Measured rate, counted over about a second
+    gens_per_sec: f64,
+    rate_gens: u64,
+    rate_start: Instant,
+    dirty: bool,
+    // Text typed after ':', None outside of command mode
+    command: Option<String>,
+    // Search typed in the pattern library popup, None while it is closed
+    library: Option<String>,
+    library_index: usize,
+    // Result of the last command
+    message: String,
+    // Generation a running jump stops at
+    jump_to: Option<u64>,
+    timeline: Timeline,
+    // Left button erases instead of toggling
+    erase: bool,
+    // State painted by the current stroke
+    stroke: u8,
+    tool: Tool,
+    brush: Brush,
+    // Tool of the current stroke, its first and last cell
+    stroke_tool: Tool,
+    drag: Option<((i64, i64), (i64, i64))>,
+    // Shape drawn when the mouse is released
+    preview: Vec<(i64, i64)>,
+    selection: Option<Vec4<i64>>,
+    // Corner the selection is being dragged from
+    select_from: Option<(i64, i64)>,
+    clipboard: Option<Pattern>,
+    // Live cells of the clipboard for drawing the paste preview
+    clipboard_cells: HashSet<(i64, i64)>,
+    // Clipboard follows the mouse until clicked into place
+    pasting: bool,
 }
 
 impl App {
     pub fn new() -> Result<App> {
         let (w, h) = crossterm::terminal::size()?;
 
-        let a = App {
+        let mut a = App {
             run: true,
             win_info_init: Vec2 { x: w, y: h },
             win_info: Vec4 {
@@ -40,9 +149,37 @@ impl App {
                 y2: h as i64,
             },
             out: stdout(),
-            world: World::new(),
-            mouse_pos: Vec2::new(0, 0)
+            world: Box::new(World::new()),
+            step_exp: 0,
+            mouse_pos: Vec2::new(0, 0),
+            playing: false,
+            speed: 16,
+            last_tick: Instant::now(),
+            owed: 0.0,
+            gens_per_sec: 0.0,
+            rate_gens: 0,
+            rate_start: Instant::now(),
+            dirty: false,
+            command: None,
+            library: None,
+            library_index: 0,
+            message: String::new(),
+            jump_to: None,
+            timeline: Timeline::new(TIMELINE_LENGTH, TIMELINE_BYTES),
+            erase: false,
+            stroke: 1,
+            tool: Tool::Pen,
+            brush: Brush::new(),
+            stroke_tool: Tool::Pen,
+            drag: None,
+            preview: Vec::new(),
+            selection: None,
+            select_from: None,
+            clipboard: None,
+            clipboard_cells: HashSet::new(),
+            pasting: false,
         };  
+        a.world.set_history(true);
         Ok(a)
     }
     pub fn start(&mut self) -> Result<()> {
@@ -59,6 +196,9 @@ impl App {
     }
 
     pub fn exit(&mut self) {
+        // Keep the session for next time, nothing to tell the user on failure
+        // as the screen is going away
+        let _ = self.save_session(&autosave_path());
         disable_raw_mode().unwrap();
         execute!(   
             self.out,
@@ -86,18 +226,59 @@ impl App {
         // Draw cells
         let (data, data_time) = self.world.get_world(self.win_info);
         let win_size = self.win_info.size();
+        let states = self.world.rule().states();
         for y in 0..win_size.y {
             queue!(self.out, MoveTo(0, y as u16)).unwrap();
             for x in 0..win_size.x {
                 let cell = data[(x + (y * win_size.x)) as usize];
-                if cell {
-                    queue!(self.out, SetForegroundColor(Color::Cyan)).unwrap();
-                } else {
-                    queue!(self.out, SetForegroundColor(Color::Black)).unwrap();
-                }
+                queue!(self.out, SetForegroundColor(state_color(cell, states))).unwrap();
                 queue!(self.out, Print("#")).unwrap();
             }
         }
+        // Shape being dragged
+        let preview_color = if self.stroke == 0 { Color::DarkRed } else { Color::Yellow };
+        queue!(self.out, SetForegroundColor(preview_color)).unwrap();
+        for (x, y) in self.preview.iter() {
+            let (x, y) = (x - self.win_info.x1, y - self.win_info.y1);
+            if x >= 0 && x < win_size.x && y >= 0 && y < win_size.y {
+                queue!(self.out, MoveTo(x as u16, y as u16), Print("#")).unwrap();
+            }
+        }
+        // Selected cells keep their colour on a highlighted background
+        if let Some(rect) = self.selection.and_then(|rect| rect.intersect(&self.win_info)) {
+            let rect = rect.local_to(&self.win_info);
+            queue!(self.out, SetBackgroundColor(Color::DarkBlue)).unwrap();
+            for y in rect.y1..rect.y2 {
+                queue!(self.out, MoveTo(rect.x1 as u16, y as u16)).unwrap();
+                for x in rect.x1..rect.x2 {
+                    let cell = data[(x + (y * win_size.x)) as usize];
+                    queue!(self.out, SetForegroundColor(state_color(cell, states)), Print("#")).unwrap();
+                }
+            }
+            queue!(self.out, SetBackgroundColor(Color::Reset)).unwrap();
+        }
+        // Clipboard under the mouse, dead cells of it on a grey background
+        if let Some(pattern) = self.clipboard.as_ref().filter(|_| self.pasting) {
+            let (x, y) = self.cell_at(self.mouse_pos.x, self.mouse_pos.y);
+            let rect = Vec4 { x1: x, y1: y, x2: x + pattern.width, y2: y + pattern.height };
+            if let Some(rect) = rect.intersect(&self.win_info) {
+                let rect = rect.local_to(&self.win_info);
+                let (mx, my) = (self.mouse_pos.x as i64, self.mouse_pos.y as i64);
+                queue!(self.out, SetBackgroundColor(Color::DarkGrey)).unwrap();
+                for y in rect.y1..rect.y2 {
+                    queue!(self.out, MoveTo(rect.x1 as u16, y as u16)).unwrap();
+                    for x in rect.x1..rect.x2 {
+                        let col = if self.clipboard_cells.contains(&(x - mx, y - my)) {
+                            Color::Yellow
+                        } else {
+                            Color::Black
+                        };
+                        queue!(self.out, SetForegroundColor(col), Print("#")).unwrap();
+                    }
+                }
+                queue!(self.out, SetBackgroundColor(Color::Reset)).unwrap();
+            }
+        }
         // Draw info
         let world_size = self.world.size();
         let win_str = format!(
@@ -105,34 +286,433 @@ impl App {
             self.win_info.x1, self.win_info.y1, win_size.x, win_size.y
         );
         let wrld_str = format!(
-            "WORLD  X1:{} Y1:{} X2:{} Y2:{}",
-            world_size.x1, world_size.y1, world_size.x2, world_size.y2
+            "WORLD  GEN:{}  X1:{} Y1:{} X2:{} Y2:{}",
+            self.world.generation(), world_size.x1, world_size.y1, world_size.x2, world_size.y2
         );
         let time_str = format!(
             "GETTING WORLD:{}us  DRAW:{}ms",
             data_time.as_micros(), draw_time.elapsed().as_millis()
         );
-        queue!(
-            self.out,
-            SetForegroundColor(Color::White),
-            MoveTo(
-                (win_size.x - time_str.len() as i64) as u16,
-                (win_size.y - 3) as u16
-            ),
-            Print(time_str),
-            MoveTo(
-                (win_size.x - wrld_str.len() as i64) as u16,
-                (win_size.y - 2) as u16
-            ),
-            Print(wrld_str),
-            MoveTo(
-                (win_size.x - win_str.len() as i64) as u16,
-                (win_size.y - 1) as u16
+        let rule = self.world.rule();
+        let rule_str = match rule.name() {
+            Some(name) => format!("RULE:{} ({})", rule, name),
+            None => format!("RULE:{}", rule),
+        };
+        let engine_str = format!(
+            "ENGINE:{}  STEP:2^{}  MODE:{}",
+            self.world.name(), self.step_exp, if self.erase { "ERASE" } else { "DRAW" }
+        );
+        let play_str = format!(
+            "{}  SPEED:{}/s  GENS/S:{:.1}",
+            if self.playing { "PLAYING" } else { "PAUSED" },
+            self.speed, self.gens_per_sec
+        );
+        let mut tool_str = format!(
+            "TOOL:{}  BRUSH:{} {}",
+            self.tool.name(),
+            self.brush.size,
+            match self.brush.shape {
+                BrushShape::Square => "SQUARE",
+                BrushShape::Round => "ROUND",
+            }
+        );
+        if let Some(rect) = self.selection {
+            let size = rect.size();
+            tool_str = format!("SELECTION:{}x{}  {}", size.x, size.y, tool_str);
+        }
+        let memory = self.world.memory();
+        let mem_str = format!(
+            "MEMORY:{}KiB  BLOCKS:{}",
+            memory.bytes / 1024, memory.blocks
+        );
+        let timeline_str = match (self.timeline.generation(0), self.timeline.len()) {
+            (Some(first), len) => format!(
+                "TIMELINE:{}-{}  {}KiB",
+                first,
+                self.timeline.generation(len - 1).unwrap(),
+                self.timeline.memory() / 1024
             ),
-            Print(win_str)
-        )
-        .unwrap();
+            (None, _) => "TIMELINE:EMPTY".to_string(),
+        };
+        // Statistics
+        let box_str = match self.world.bounding_box() {
+            Some(rect) => format!("X:{} Y:{} W:{} H:{}", rect.x1, rect.y1, rect.x2 - rect.x1, rect.y2 - rect.y1),
+            None => "EMPTY".to_string(),
+        };
+        let pop_str = format!("POPULATION:{}  BOX {}", self.world.population(), box_str);
+        let (growth_str, spark_str) = match self.world.stats() {
+            Some(stats) => {
+                let populations: Vec<u64> = stats.populations().collect();
+                (
+                    format!("BIRTHS:{}  DEATHS:{}", stats.last_step.births, stats.last_step.deaths),
+                    format!("POP {}", sparkline(&populations, SPARKLINE_WIDTH)),
+                )
+            }
+            None => ("BIRTHS:-  DEATHS:-".to_string(), String::new()),
+        };
+        // Status lines from above the timeline bar upwards
+        let lines = [
+            win_str, wrld_str, time_str, rule_str, engine_str, tool_str, mem_str, play_str, timeline_str,
+            spark_str, growth_str, pop_str,
+        ];
+        queue!(self.out, SetForegroundColor(Color::White)).unwrap();
+        for (i, line) in lines.iter().enumerate() {
+            queue!(
+                self.out,
+                MoveTo(
+                    (win_size.x - line.chars().count() as i64) as u16,
+                    (win_size.y - 2 - i as i64) as u16
+                ),
+                Print(line)
+            )
+            .unwrap();
+        }
+        // Command line in bottom left corner
+        let command_str = match (&self.command, self.jump_to) {
+            (Some(command), _) => format!(":{}_", command),
+            (None, Some(target)) => format!("JUMPING TO GEN:{}  (ESC CANCELS)", target),
+            (None, None) => self.message.clone(),
+        };
+        queue!(self.out, MoveTo(0, (win_size.y - 2) as u16), Print(command_str)).unwrap();
+        self.draw_timeline();
+        self.draw_library();
         self.out.flush().unwrap();
+        self.dirty = false;
+    }
+    // Bottom row, recorded generations spread over the whole width with
+    // the current one marked
+    fn draw_timeline(&mut self) {
+        let width = self.win_info.size().x;
+        let row = (self.win_info.size().y - 1) as u16;
+        queue!(self.out, MoveTo(0, row)).unwrap();
+        let len = self.timeline.len() as i64;
+        let current = self.timeline.position(self.world.generation());
+        for x in 0..width {
+            let index = x * len / width.max(1);
+            let (col, c) = match len {
+                0 => (Color::DarkGrey, ' '),
+                _ if Some(index as usize) == current => (Color::Yellow, '|'),
+                _ => (Color::DarkGrey, '='),
+            };
+            queue!(self.out, SetForegroundColor(col), Print(c)).unwrap();
+        }
+    }
+    // Popup over the middle of the grid listing the library entries that
+    // match the search
+    fn draw_library(&mut self) {
+        let query = match &self.library {
+            Some(query) => query.clone(),
+            None => return,
+        };
+        let entries = search_library(&query);
+        let win_size = self.win_info.size();
+        let width = win_size.x.min(48);
+        let height = (entries.len() as i64 + 3).min(win_size.y - 4).max(3);
+        let (x, y) = ((win_size.x - width) / 2, (win_size.y - height) / 2);
+        self.draw_rect(x as u16, y as u16, width as u16, height as u16, Color::DarkBlue);
+        let rows = (height - 3) as usize;
+        // Scroll just enough to keep the highlighted entry visible
+        let first = (self.library_index + 1).saturating_sub(rows);
+        let mut lines = vec![(format!("LIBRARY  SEARCH:{}_", query), Color::White)];
+        for (i, entry) in entries.iter().enumerate().skip(first).take(rows) {
+            let col = if i == self.library_index { Color::Yellow } else { Color::Grey };
+            lines.push((format!("{:<26}{}", entry.name, entry.category.to_uppercase()), col));
+        }
+        while lines.len() < height as usize - 1 {
+            lines.push((String::new(), Color::Grey));
+        }
+        lines.push(("ENTER PLACES  ESC CLOSES".to_string(), Color::White));
+        for (i, (line, col)) in lines.iter().enumerate() {
+            let line: String = line.chars().take(width as usize - 2).collect();
+            queue!(self.out, MoveTo(x as u16 + 1, (y + i as i64) as u16), SetForegroundColor(*col), Print(line)).unwrap();
+        }
+        queue!(self.out, SetBackgroundColor(Color::Reset)).unwrap();
+    }
+    fn handle_library_key(&mut self, k: KeyCode) {
+        let query = self.library.as_mut().unwrap();
+        let count = search_library(query).len();
+        match k {
+            KeyCode::Char(c) => {
+                query.push(c);
+                self.library_index = 0;
+            }
+            KeyCode::Backspace => {
+                query.pop();
+                self.library_index = 0;
+            }
+            KeyCode::Up => self.library_index = self.library_index.saturating_sub(1),
+            KeyCode::Down => self.library_index = (self.library_index + 1).min(count.saturating_sub(1)),
+            // Chosen pattern follows the mouse like a paste
+            KeyCode::Enter => {
+                if let Some(entry) = search_library(query).get(self.library_index) {
+                    self.set_clipboard(entry.pattern());
+                    self.pasting = true;
+                    self.message = format!("CLICK TO PLACE {}, ESC CANCELS", entry.name.to_uppercase());
+                    self.library = None;
+                }
+            }
+            KeyCode::Esc => self.library = None,
+            _ => {}
+        }
+        self.draw();
+    }
+    // Every world shown starts with a fresh undo history
+    fn set_world(&mut self, world: Box<dyn LifeEngine>) {
+        self.world = world;
+        self.world.set_history(true);
+    }
+    // Restore the generation under a column of the timeline bar, undoable
+    // on engines with history
+    fn scrub(&mut self, column: u16) {
+        let width = self.win_info.size().x.max(1);
+        let index = (column as i64 * self.timeline.len() as i64 / width) as usize;
+        if let Some(world) = self.timeline.restore(index, self.world.as_ref()) {
+            if !self.world.replace(world.as_ref()) {
+                self.set_world(world);
+            }
+            self.playing = false;
+            self.jump_to = None;
+            self.message = format!("REWOUND TO GEN:{}", self.world.generation());
+        }
+        self.draw();
+    }
+    fn on_timeline(&self, row: u16) -> bool {
+        row as i64 == self.win_info.size().y - 1
+    }
+    // Steps go through here so every generation is recorded, including
+    // the one stepping starts from
+    fn step(&mut self) {
+        self.record_start();
+        self.world.life_step();
+        self.timeline.record(self.world.as_ref());
+    }
+    fn record_start(&mut self) {
+        if self.timeline.position(self.world.generation()).is_none() {
+            self.timeline.record(self.world.as_ref());
+        }
+    }
+    // Jumps are recorded where they start and end, not at every step
+    fn start_jump(&mut self, target: u64) {
+        self.record_start();
+        self.jump_to = Some(target);
+    }
+    fn end_jump(&mut self) {
+        self.jump_to = None;
+        self.timeline.record(self.world.as_ref());
+        self.dirty = true;
+    }
+    // Redraw if anything changed since the last frame
+    pub fn frame(&mut self) {
+        if self.dirty {
+            self.draw();
+        }
+    }
+    // Advance the simulation by the generations due since the last tick
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        if let Some(target) = self.jump_to {
+            self.jump(target, now);
+        } else if self.playing {
+            self.owed += (now - self.last_tick).as_secs_f64() * self.speed as f64;
+            while self.owed >= 1.0 {
+                self.step();
+                self.owed -= 1.0;
+                self.rate_gens += 1;
+                self.dirty = true;
+                if now.elapsed() > TICK_BUDGET {
+                    // Can't keep up, run as fast as possible instead
+                    self.owed = 0.0;
+                }
+            }
+        }
+        self.last_tick = now;
+        let rate_time = self.rate_start.elapsed();
+        if rate_time >= Duration::from_secs(1) {
+            self.gens_per_sec = self.rate_gens as f64 / rate_time.as_secs_f64();
+            self.rate_gens = 0;
+            self.rate_start = now;
+            self.dirty = true;
+        }
+    }
+    // Step towards target until the tick budget runs out. Nothing is drawn
+    // until the jump is done
+    fn jump(&mut self, target: u64, start: Instant) {
+        while self.world.generation() < target && start.elapsed() < TICK_BUDGET {
+            let generation = self.world.generation();
+            let remaining = target - generation;
+            if self.world.fast_pow2() && remaining > 1 {
+                // Smaller steps are tried when the engine refuses one
+                let mut exp = (63 - remaining.leading_zeros()).min(MAX_JUMP_EXP);
+                while !self.world.step_pow2(exp as u8) && exp > 0 {
+                    exp -= 1;
+                }
+            } else {
+                self.world.life_step();
+            }
+            let stepped = self.world.generation() - generation;
+            if stepped == 0 {
+                self.end_jump();
+                self.message = format!("ERROR: can't step past GEN:{}", generation);
+                return;
+            }
+            self.rate_gens += stepped;
+        }
+        if self.world.generation() >= target {
+            self.end_jump();
+            self.message = format!("AT GEN:{}", self.world.generation());
+        }
+    }
+    fn run_command(&mut self, text: &str) {
+        let command = match Command::parse(text) {
+            Ok(command) => command,
+            Err(err) => {
+                self.message = format!("ERROR: {}", err);
+                return;
+            }
+        };
+        let generation = self.world.generation();
+        match command {
+            Command::Step(n) => match generation.checked_add(n) {
+                Some(target) => self.start_jump(target),
+                None => self.message = "ERROR: generation counter would overflow".to_string(),
+            },
+            Command::Goto(target) if target < generation => {
+                self.message = format!("ERROR: already at generation {}", generation);
+            }
+            Command::Goto(target) => self.start_jump(target),
+            Command::Rule(rule) => {
+                self.world.set_rule(rule);
+                self.message = format!("RULE SET TO {}", rule);
+            }
+            // Selection or a standard soup in the middle of the window
+            Command::Soup { seed, density } => {
+                let seed = seed.unwrap_or_else(random_seed);
+                let rect = self.selection.unwrap_or_else(|| {
+                    let size = self.win_info.size();
+                    let x = self.win_info.x1 + (size.x - SOUP_SIZE) / 2;
+                    let y = self.win_info.y1 + (size.y - SOUP_SIZE) / 2;
+                    Vec4 { x1: x, y1: y, x2: x + SOUP_SIZE, y2: y + SOUP_SIZE }
+                });
+                let size = rect.size();
+                self.world.commit_edit();
+                place_soup(self.world.as_mut(), rect, density as f64 / 100.0, seed);
+                self.edited(&format!("SOUP SEED:{} DENSITY:{}% {}x{}", seed, density, size.x, size.y));
+            }
+        }
+    }
+    fn handle_command_key(&mut self, k: KeyCode) {
+        let command = self.command.as_mut().unwrap();
+        match k {
+            KeyCode::Char(c) => command.push(c),
+            // Deleting past the ':' leaves command mode
+            KeyCode::Backspace if command.is_empty() => self.command = None,
+            KeyCode::Backspace => {
+                command.pop();
+            }
+            KeyCode::Enter => {
+                let text = self.command.take().unwrap();
+                self.run_command(&text);
+            }
+            KeyCode::Esc => self.command = None,
+            _ => {}
+        }
+        self.draw();
+    }
+    pub fn toggle_play(&mut self) {
+        self.playing = !self.playing;
+        self.owed = 0.0;
+        self.draw();
+    }
+    pub fn change_speed(&mut self, faster: bool) {
+        self.speed = match faster {
+            true => (self.speed * 2).min(MAX_SPEED),
+            false => (self.speed / 2).max(1),
+        };
+        self.draw();
+    }
+    // Load pattern file centred on 0, 0 and look at it. Macrocells keep
+    // their own coordinates and replace a HashLife world as a whole
+    pub fn open(&mut self, path: &Path) -> std::result::Result<(), String> {
+        let text = read_file(path)?;
+        let in_file = |err| format!("{}: {}", path.display(), err);
+        let name = if Format::detect(&text) == Format::Macrocell {
+            let life = read_macrocell(&text).map_err(in_file)?;
+            // Copying cell by cell, each one an undo edit, only pays off
+            // for small patterns
+            if self.world.quadtree().is_some() || life.population() > MAX_TRANSFER_CELLS {
+                self.set_world(Box::new(life));
+            } else {
+                self.world.commit_edit();
+                transfer(&life, self.world.as_mut());
+                self.world.commit_edit();
+            }
+            None
+        } else {
+            let pattern = read_pattern(&text).map_err(in_file)?;
+            if let Some(rule) = pattern.rule {
+                self.world.set_rule(rule);
+            }
+            pattern.place(self.world.as_mut(), -pattern.width / 2, -pattern.height / 2);
+            pattern.name
+        };
+        self.timeline.record(self.world.as_ref());
+        let win_size = self.win_info.size();
+        self.win_info = Vec4 {
+            x1: -win_size.x / 2,
+            y1: -win_size.y / 2,
+            x2: win_size.x - win_size.x / 2,
+            y2: win_size.y - win_size.y / 2,
+        };
+        self.message = match name {
+            Some(name) => format!("OPENED {} ({})", path.display(), name),
+            None => format!("OPENED {}", path.display()),
+        };
+        Ok(())
+    }
+    pub fn session(&self) -> Session {
+        Session {
+            engine: self.world.name().to_string(),
+            window: self.win_info,
+            step_exp: self.step_exp,
+            speed: self.speed,
+            playing: self.playing,
+        }
+    }
+    pub fn save_session(&self, path: &Path) -> std::result::Result<(), String> {
+        let text = self.session().write(self.world.as_ref());
+        std::fs::write(path, text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+    // Window keeps the current terminal size
+    pub fn restore_session(&mut self, path: &Path) -> std::result::Result<(), String> {
+        let text = read_file(path)?;
+        let (session, world) = Session::read(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let win_size = self.win_info.size();
+        self.win_info = Vec4 {
+            x1: session.window.x1,
+            y1: session.window.y1,
+            x2: session.window.x1 + win_size.x,
+            y2: session.window.y1 + win_size.y,
+        };
+        self.set_world(world);
+        self.timeline.clear();
+        self.timeline.record(self.world.as_ref());
+        self.step_exp = session.step_exp.min(62);
+        self.speed = session.speed.clamp(1, MAX_SPEED);
+        self.playing = session.playing;
+        self.owed = 0.0;
+        self.jump_to = None;
+        Ok(())
+    }
+    // Continue from the last exit, when there is one
+    pub fn restore_autosave(&mut self) {
+        let path = autosave_path();
+        if !path.exists() {
+            return;
+        }
+        self.message = match self.restore_session(&path) {
+            Ok(()) => format!("AUTOSAVE RESTORED {}", path.display()),
+            Err(err) => format!("ERROR: {}", err),
+        };
     }
     pub fn move_window(&mut self, x: i64, y: i64) {
         self.win_info.x1 += x;
@@ -142,14 +722,90 @@ impl App {
         self.draw();
     }
 
-    pub fn handle_key(&mut self, k: KeyCode) {
-        match k {
+    pub fn next_rule(&mut self) {
+        // Cycle through preset rules
+        let current = RULE_PRESETS
+            .iter()
+            .position(|preset| Rule::parse(preset.1) == Ok(self.world.rule()));
+        let next = current.map_or(0, |i| (i + 1) % RULE_PRESETS.len());
+        self.world.set_rule(Rule::parse(RULE_PRESETS[next].1).unwrap());
+        self.draw();
+    }
+
+    pub fn handle_key(&mut self, k: KeyEvent) {
+        if self.command.is_some() {
+            self.handle_command_key(k.code);
+            return;
+        }
+        if self.library.is_some() {
+            self.handle_library_key(k.code);
+            return;
+        }
+        if k.modifiers.contains(KeyModifiers::CONTROL) {
+            let path = session_path();
+            let result = match k.code {
+                KeyCode::Char('s') => self.save_session(&path).map(|_| format!("SESSION SAVED {}", path.display())),
+                KeyCode::Char('o') => self.restore_session(&path).map(|_| format!("SESSION RESTORED {}", path.display())),
+                KeyCode::Char('z') => Ok(if self.world.undo() { "UNDONE" } else { "NOTHING TO UNDO" }.to_string()),
+                KeyCode::Char('y') => Ok(if self.world.redo() { "REDONE" } else { "NOTHING TO REDO" }.to_string()),
+                KeyCode::Char('c') => self.copy().map(|_| "COPIED".to_string()),
+                KeyCode::Char('x') => self.cut().map(|_| "CUT".to_string()),
+                KeyCode::Char('v') => self.start_paste().map(|_| "CLICK TO PASTE, ESC CANCELS".to_string()),
+                _ => return,
+            };
+            self.message = result.unwrap_or_else(|err| format!("ERROR: {}", err));
+            self.draw();
+            return;
+        }
+        match k.code {
             KeyCode::Enter => {
-                self.world.life_step();
+                self.step();
+                self.draw();
+            }
+            KeyCode::Esc if self.jump_to.is_some() => {
+                self.end_jump();
+                self.message = format!("JUMP CANCELLED AT GEN:{}", self.world.generation());
+                self.draw();
+            }
+            KeyCode::Esc if self.pasting => {
+                self.pasting = false;
+                self.message = "PASTE CANCELLED".to_string();
+                self.draw();
+            }
+            KeyCode::Esc if self.selection.is_some() => {
+                self.selection = None;
+                self.draw();
             }
             KeyCode::Esc => {
                 self.exit();
             }
+            KeyCode::Delete | KeyCode::Backspace => {
+                if let Some(rect) = self.selection {
+                    clear_rect(self.world.as_mut(), rect);
+                    self.edited("CLEARED");
+                }
+            }
+            KeyCode::Char('>') => self.transform(Pattern::rotate_cw, "ROTATED CLOCKWISE"),
+            KeyCode::Char('<') => self.transform(Pattern::rotate_ccw, "ROTATED COUNTERCLOCKWISE"),
+            KeyCode::Char('^') => self.transform(Pattern::rotate_180, "ROTATED 180"),
+            KeyCode::Char('|') => self.transform(Pattern::flip_horizontal, "MIRRORED HORIZONTALLY"),
+            KeyCode::Char('_') => self.transform(Pattern::flip_vertical, "MIRRORED VERTICALLY"),
+            KeyCode::Char('\\') => self.transform(Pattern::transpose, "TRANSPOSED"),
+            KeyCode::Char('f') => {
+                if let Some(rect) = self.selection {
+                    fill_rect(self.world.as_mut(), rect, 1);
+                    self.edited("FILLED");
+                }
+            }
+            KeyCode::Char(':') => {
+                self.command = Some(String::new());
+                self.draw();
+            }
+            KeyCode::Char('l') => {
+                self.library = Some(String::new());
+                self.library_index = 0;
+                self.draw();
+            }
             KeyCode::Left => {
                 self.move_window(-2, 0);
             }
@@ -162,11 +818,63 @@ impl App {
             KeyCode::Down => {
                 self.move_window(0, 2);
             }
-            KeyCode::Char(c) => {
-                match c {
-                    _ => {}
+            KeyCode::Char('r') => {
+                self.next_rule();
+            }
+            KeyCode::Char('e') => {
+                self.erase = !self.erase;
+                self.draw();
+            }
+            KeyCode::Char('t') => {
+                self.tool = self.tool.next();
+                self.draw();
+            }
+            KeyCode::Char('o') => {
+                self.brush.shape = match self.brush.shape {
+                    BrushShape::Square => BrushShape::Round,
+                    BrushShape::Round => BrushShape::Square,
+                };
+                self.draw();
+            }
+            KeyCode::Char('{') => {
+                self.brush.size = (self.brush.size - 1).max(1);
+                self.draw();
+            }
+            KeyCode::Char('}') => {
+                self.brush.size = (self.brush.size + 1).min(MAX_BRUSH);
+                self.draw();
+            }
+            KeyCode::Tab => {
+                let mut next = next_engine(self.world.as_ref());
+                transfer(self.world.as_ref(), next.as_mut());
+                self.set_world(next);
+                self.draw();
+            }
+            KeyCode::Char('[') => {
+                self.step_exp = self.step_exp.saturating_sub(1);
+                self.draw();
+            }
+            KeyCode::Char(']') => {
+                self.step_exp = (self.step_exp + 1).min(62);
+                self.draw();
+            }
+            KeyCode::Char(' ') => {
+                self.toggle_play();
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.change_speed(true);
+            }
+            KeyCode::Char('-') => {
+                self.change_speed(false);
+            }
+            // Slow engines step one generation at a time, so this goes
+            // through a jump that Esc can cancel
+            KeyCode::Char('.') => {
+                match self.world.generation().checked_add(1 << self.step_exp) {
+                    Some(target) => self.start_jump(target),
+                    None => self.message = "ERROR: generation counter would overflow".to_string(),
                 }
-                {}
+                self.draw();
             }
             _ => {}
         }
@@ -177,6 +885,7 @@ impl App {
             MouseEventKind::Moved =>{
                 self.mouse_pos.x = ev.column;
                 self.mouse_pos.y = ev.row;
+                self.dirty |= self.pasting;
             },
             MouseEventKind::Drag(b) => {
                 if b == MouseButton::Right{
@@ -188,33 +897,176 @@ impl App {
                 self.mouse_pos.x = ev.column;
                 self.mouse_pos.y = ev.row;
 
-                if b == MouseButton::Left{
-                    let cell_x= ev.column as i64 + self.win_info.x1;
-                    let cell_y = ev.row as i64 + self.win_info.y1;
-                    self.world.set_cell(cell_x, cell_y, true);
-                    self.draw();
+                match b {
+                    MouseButton::Left if self.select_from.is_some() => {
+                        self.selection = self.select_from.map(|from| span(from, self.cell_at(ev.column, ev.row)));
+                        self.draw();
+                    }
+                    MouseButton::Left if self.drag.is_none() && self.on_timeline(ev.row) => self.scrub(ev.column),
+                    MouseButton::Left | MouseButton::Middle => self.drag_to(self.cell_at(ev.column, ev.row)),
+                    MouseButton::Right => {}
                 }
             }
+            MouseEventKind::Up(MouseButton::Left | MouseButton::Middle) => {
+                self.select_from = None;
+                self.end_stroke();
+            }
             MouseEventKind::Down(b) => {
+                self.mouse_pos.x = ev.column;
+                self.mouse_pos.y = ev.row;
+                let cell = self.cell_at(ev.column, ev.row);
                 match b{
-                    MouseButton::Left=>{
-                        // Local to absolute cells coords
-                        let cell_x= ev.column as i64 + self.win_info.x1;
-                        let cell_y = ev.row as i64 + self.win_info.y1;
-                        self.world.set_cell(cell_x, cell_y, true);
+                    MouseButton::Left if self.on_timeline(ev.row) => {
+                        self.scrub(ev.column);
+                    }
+                    MouseButton::Left if self.pasting => {
+                        self.paste(cell);
+                    }
+                    MouseButton::Left if ev.modifiers.contains(KeyModifiers::SHIFT) => {
+                        self.select_from = Some(cell);
+                        self.selection = Some(span(cell, cell));
                         self.draw();
                     }
-                    MouseButton::Right=>{
-
+                    MouseButton::Left=>{
+                        // Clicked cell decides whether the stroke draws or erases
+                        let state = match (self.erase, self.tool) {
+                            (true, _) => 0,
+                            (false, Tool::Pen) => (self.world.get_cell(cell.0, cell.1) == 0) as u8,
+                            (false, _) => 1,
+                        };
+                        self.start_stroke(self.tool, state, cell);
                     }
+                    // Right button pans from here on
+                    MouseButton::Right=>{}
                     MouseButton::Middle=>{
-
+                        self.start_stroke(Tool::Pen, 0, cell);
                     }
                 }
             }
             _ => {}
         }
     }
+    fn copy(&mut self) -> std::result::Result<(), String> {
+        let rect = self.selection.ok_or("NOTHING SELECTED")?;
+        let pattern = Pattern::from_world(self.world.as_ref(), rect);
+        // Terminals without OSC 52 support ignore it
+        queue!(self.out, Print(osc52(&write_rle(&pattern)))).unwrap();
+        self.set_clipboard(pattern);
+        Ok(())
+    }
+    fn set_clipboard(&mut self, pattern: Pattern) {
+        self.clipboard_cells = pattern.cells.iter().map(|(x, y, _)| (*x, *y)).collect();
+        self.clipboard = Some(pattern);
+    }
+    fn cut(&mut self) -> std::result::Result<(), String> {
+        self.copy()?;
+        if let Some(rect) = self.selection {
+            clear_rect(self.world.as_mut(), rect);
+            self.edited("CUT");
+        }
+        Ok(())
+    }
+    fn start_paste(&mut self) -> std::result::Result<(), String> {
+        self.clipboard.as_ref().ok_or("CLIPBOARD EMPTY")?;
+        self.pasting = true;
+        Ok(())
+    }
+    // Clipboard goes with its top left corner at the cell and becomes
+    // the selection
+    fn paste(&mut self, (x, y): (i64, i64)) {
+        let pattern = match self.clipboard.take() {
+            Some(pattern) => pattern,
+            None => return,
+        };
+        self.pasting = false;
+        self.world.commit_edit();
+        pattern.place(self.world.as_mut(), x, y);
+        self.selection = Some(Vec4 { x1: x, y1: y, x2: x + pattern.width, y2: y + pattern.height });
+        self.clipboard = Some(pattern);
+        self.edited("PASTED");
+    }
+    // Transform the clipboard while pasting, otherwise the selected cells
+    // in place, keeping the top left corner
+    fn transform(&mut self, f: fn(&mut Pattern), message: &str) {
+        if self.pasting {
+            if let Some(mut pattern) = self.clipboard.take() {
+                f(&mut pattern);
+                self.set_clipboard(pattern);
+            }
+            self.message = message.to_string();
+            self.draw();
+            return;
+        }
+        let rect = match self.selection {
+            Some(rect) => rect,
+            None => {
+                self.message = "NOTHING SELECTED".to_string();
+                self.draw();
+                return;
+            }
+        };
+        let mut pattern = Pattern::from_world(self.world.as_ref(), rect);
+        f(&mut pattern);
+        self.world.commit_edit();
+        clear_rect(self.world.as_mut(), rect);
+        pattern.place(self.world.as_mut(), rect.x1, rect.y1);
+        self.selection = Some(Vec4 {
+            x1: rect.x1,
+            y1: rect.y1,
+            x2: rect.x1 + pattern.width,
+            y2: rect.y1 + pattern.height,
+        });
+        self.edited(message);
+    }
+    // Close the edit for undo and record it in the timeline
+    fn edited(&mut self, message: &str) {
+        self.world.commit_edit();
+        self.timeline.record(self.world.as_ref());
+        self.message = message.to_string();
+        self.draw();
+    }
+    // Local to absolute cells coords
+    fn cell_at(&self, column: u16, row: u16) -> (i64, i64) {
+        (column as i64 + self.win_info.x1, row as i64 + self.win_info.y1)
+    }
+    fn start_stroke(&mut self, tool: Tool, state: u8, cell: (i64, i64)) {
+        // Every stroke is undone on its own
+        self.world.commit_edit();
+        self.stroke = state;
+        self.stroke_tool = tool;
+        self.drag = Some((cell, cell));
+        self.drag_to(cell);
+    }
+    // Pen paints along the line from the last mouse position so fast
+    // moves leave no gaps, shapes are only previewed until released
+    fn drag_to(&mut self, cell: (i64, i64)) {
+        let (start, last) = match self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        match self.stroke_tool {
+            Tool::Pen => {
+                for (x, y) in self.brush.paint(&line(last, cell)) {
+                    self.world.set_state(x, y, self.stroke);
+                }
+            }
+            tool => self.preview = self.brush.paint(&tool.shape(start, cell)),
+        }
+        self.drag = Some((start, cell));
+        self.draw();
+    }
+    fn end_stroke(&mut self) {
+        if self.drag.take().is_none() {
+            return;
+        }
+        for (x, y) in std::mem::take(&mut self.preview) {
+            self.world.set_state(x, y, self.stroke);
+        }
+        self.world.commit_edit();
+        // Finished stroke replaces the recorded generation
+        self.timeline.record(self.world.as_ref());
+        self.draw();
+    }
     
     pub fn handle_resize(&mut self, w: u16, h:u16){
         self.win_info.x2 = self.win_info.x1 + w as i64;
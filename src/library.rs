@@ -0,0 +1,59 @@
+use crate::pattern::Pattern;
+use crate::rle::read_rle;
+
+// Pattern bundled with the crate, RLE files live in patterns/
+pub struct LibraryEntry {
+    pub name: &'static str,
+    pub category: &'static str,
+    pub rle: &'static str,
+}
+impl LibraryEntry {
+    pub fn pattern(&self) -> Pattern {
+        // Bundled files are checked by the tests
+        read_rle(self.rle).unwrap()
+    }
+}
+
+macro_rules! entry {
+    ($name:expr, $category:expr, $file:expr) => {
+        LibraryEntry {
+            name: $name,
+            category: $category,
+            rle: include_str!(concat!("../patterns/", $file, ".rle")),
+        }
+    };
+}
+
+pub const LIBRARY: &[LibraryEntry] = &[
+    entry!("Block", "still life", "block"),
+    entry!("Beehive", "still life", "beehive"),
+    entry!("Loaf", "still life", "loaf"),
+    entry!("Boat", "still life", "boat"),
+    entry!("Tub", "still life", "tub"),
+    entry!("Blinker", "oscillator", "blinker"),
+    entry!("Toad", "oscillator", "toad"),
+    entry!("Beacon", "oscillator", "beacon"),
+    entry!("Pulsar", "oscillator", "pulsar"),
+    entry!("Pentadecathlon", "oscillator", "pentadecathlon"),
+    entry!("Glider", "spaceship", "glider"),
+    entry!("Lightweight spaceship", "spaceship", "lwss"),
+    entry!("Middleweight spaceship", "spaceship", "mwss"),
+    entry!("Heavyweight spaceship", "spaceship", "hwss"),
+    entry!("Gosper glider gun", "gun", "gosperglidergun"),
+    entry!("R-pentomino", "methuselah", "rpentomino"),
+    entry!("Diehard", "methuselah", "diehard"),
+    entry!("Acorn", "methuselah", "acorn"),
+];
+
+// Entries with every word of the query in their name or category,
+// ignoring case
+pub fn search_library(query: &str) -> Vec<&'static LibraryEntry> {
+    let query = query.to_lowercase();
+    LIBRARY
+        .iter()
+        .filter(|entry| {
+            let text = format!("{} {}", entry.name, entry.category).to_lowercase();
+            query.split_whitespace().all(|word| text.contains(word))
+        })
+        .collect()
+}
@@ -0,0 +1,86 @@
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+// Life 1.06, one 'x y' coordinate pair per alive cell
+pub fn read_life106(text: &str) -> Result<Pattern, String> {
+    let mut cells = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut numbers = line.split_whitespace().map(|n| n.parse::<i64>());
+        match (numbers.next(), numbers.next(), numbers.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => cells.push((x, y, 1)),
+            _ => return Err(format!("invalid coordinates '{}'", line)),
+        }
+    }
+    Ok(normalized(Pattern::new(), cells))
+}
+
+// Life 1.05, '#P x y' blocks of '.' and '*' rows with '#D' descriptions
+// and '#N' or '#R' rules
+pub fn read_life105(text: &str) -> Result<Pattern, String> {
+    let mut pattern = Pattern::new();
+    let mut cells = Vec::new();
+    let (mut block_x, mut y) = (0, 0);
+    for line in text.lines().map(str::trim) {
+        if let Some(line) = line.strip_prefix('#') {
+            let mut chars = line.chars();
+            let kind = chars.next();
+            let rest = chars.as_str().trim();
+            match kind {
+                Some('D') => pattern.comments.push(rest.to_string()),
+                Some('N') => pattern.rule = Some(Rule::life()),
+                Some('R') => pattern.rule = Some(Rule::parse(rest)?),
+                Some('P') => {
+                    let mut numbers = rest.split_whitespace().map(|n| n.parse::<i64>());
+                    match (numbers.next(), numbers.next()) {
+                        (Some(Ok(x)), Some(Ok(block_y))) => {
+                            block_x = x;
+                            y = block_y;
+                        }
+                        _ => return Err(format!("invalid block position '{}'", rest)),
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                '*' => cells.push((block_x + x as i64, y, 1)),
+                c => return Err(format!("unexpected '{}' in Life 1.05 pattern", c)),
+            }
+        }
+        y += 1;
+    }
+    Ok(normalized(pattern, cells))
+}
+
+// Coordinates of the top left corner go to 0, 0
+fn normalized(mut pattern: Pattern, mut cells: Vec<(i64, i64, u8)>) -> Pattern {
+    let x1 = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+    let y1 = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+    for cell in cells.iter_mut() {
+        cell.0 -= x1;
+        cell.1 -= y1;
+    }
+    cells.sort_by_key(|(x, y, _)| (*y, *x));
+    cells.dedup();
+    pattern.width = cells.iter().map(|cell| cell.0 + 1).max().unwrap_or(0);
+    pattern.height = cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0);
+    pattern.cells = cells;
+    pattern
+}
+
+// Decay states are written as alive, the format has no states
+pub fn write_life106(pattern: &Pattern) -> String {
+    let mut text = String::from("#Life 1.06\n");
+    let mut cells = pattern.cells.clone();
+    cells.sort_by_key(|(x, y, _)| (*y, *x));
+    for (x, y, _) in cells {
+        text += &format!("{} {}\n", x, y);
+    }
+    text
+}
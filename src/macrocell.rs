@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use crate::engine::{transfer, LifeEngine};
+use crate::hashlife::{HashLife, NodeId};
+use crate::rule::Rule;
+
+// Golly macrocell files are quadtrees written bottom up, one node per
+// line. Two state patterns have 8x8 leaves written as '.', '*' and '$',
+// Generations patterns have level 1 nodes holding states. Bigger nodes
+// are 'level nw ne sw se' with children as line numbers, 0 is empty
+pub fn read_macrocell(text: &str) -> Result<HashLife, String> {
+    let mut lines = text.lines().map(str::trim);
+    match lines.next() {
+        Some(line) if line.starts_with("[M2]") => {}
+        _ => return Err("missing [M2] header".to_string()),
+    }
+    let mut life = HashLife::new();
+    // Node of every line, numbered from 1
+    let mut ids: Vec<NodeId> = Vec::new();
+    for line in lines {
+        if let Some(line) = line.strip_prefix('#') {
+            let mut chars = line.chars();
+            let kind = chars.next();
+            let value = chars.as_str().trim();
+            match kind {
+                Some('R') => life.set_rule(Rule::parse(value)?),
+                Some('G') => {
+                    life.set_generation(value.parse().map_err(|_| format!("invalid generation '{}'", value))?)
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let id = if line.starts_with(['.', '*', '$']) {
+            read_leaf(&mut life, line)?
+        } else {
+            read_node(&mut life, line, &ids)?
+        };
+        ids.push(id);
+    }
+    let root = *ids.last().ok_or("macrocell file has no nodes")?;
+    life.set_root(root);
+    Ok(life)
+}
+
+fn read_leaf(life: &mut HashLife, line: &str) -> Result<NodeId, String> {
+    let mut cells = [[0u8; 8]; 8];
+    let (mut x, mut y) = (0, 0);
+    for c in line.chars() {
+        match c {
+            '.' | '*' if x < 8 && y < 8 => {
+                cells[y][x] = (c == '*') as u8;
+                x += 1;
+            }
+            '$' => {
+                x = 0;
+                y += 1;
+            }
+            _ => return Err(format!("invalid leaf '{}'", line)),
+        }
+    }
+    Ok(build(life, &cells, 0, 0, 3))
+}
+
+fn build(life: &mut HashLife, cells: &[[u8; 8]; 8], x: usize, y: usize, level: u8) -> NodeId {
+    if level == 0 {
+        return life.leaf(cells[y][x]);
+    }
+    let half = 1 << (level - 1);
+    let nw = build(life, cells, x, y, level - 1);
+    let ne = build(life, cells, x + half, y, level - 1);
+    let sw = build(life, cells, x, y + half, level - 1);
+    let se = build(life, cells, x + half, y + half, level - 1);
+    life.join(nw, ne, sw, se)
+}
+
+fn read_node(life: &mut HashLife, line: &str, ids: &[NodeId]) -> Result<NodeId, String> {
+    let numbers: Vec<usize> = line
+        .split_whitespace()
+        .map(|n| n.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("invalid node '{}'", line))?;
+    let (level, refs) = match numbers.as_slice() {
+        [level, refs @ ..] if refs.len() == 4 && *level >= 1 && *level < 63 => (*level as u8, refs),
+        _ => return Err(format!("invalid node '{}'", line)),
+    };
+    let mut children = [0; 4];
+    for (child, r) in children.iter_mut().zip(refs) {
+        *child = if level == 1 {
+            // Cell states
+            let state = u8::try_from(*r).map_err(|_| format!("invalid state {}", r))?;
+            life.leaf(state)
+        } else if *r == 0 {
+            life.empty(level - 1)
+        } else {
+            let id = *ids.get(r - 1).ok_or(format!("node {} is not defined yet", r))?;
+            if life.node(id).0 != level - 1 {
+                return Err(format!("node {} is not of level {}", r, level - 1));
+            }
+            id
+        };
+    }
+    Ok(life.join(children[0], children[1], children[2], children[3]))
+}
+
+// Write any engine, others than HashLife are converted to a quadtree first
+pub fn write_macrocell(engine: &dyn LifeEngine) -> String {
+    match engine.quadtree() {
+        Some(life) => write_quadtree(life),
+        None => {
+            let mut life = HashLife::new();
+            transfer(engine, &mut life);
+            write_quadtree(&life)
+        }
+    }
+}
+
+fn write_quadtree(life: &HashLife) -> String {
+    let mut text = String::from("[M2] (gol)\n");
+    text += &format!("#R {}\n", life.rule());
+    if life.generation() != 0 {
+        text += &format!("#G {}\n", life.generation());
+    }
+    let mut writer = Writer {
+        life,
+        multi_state: life.rule().states() > 2,
+        ids: HashMap::new(),
+        lines: Vec::new(),
+    };
+    if writer.write(life.root()) == 0 {
+        // Empty pattern still needs a node
+        writer.lines.push("$".to_string());
+    }
+    for line in writer.lines {
+        text += &line;
+        text.push('\n');
+    }
+    text
+}
+
+struct Writer<'a> {
+    life: &'a HashLife,
+    multi_state: bool,
+    // Line numbers of written nodes
+    ids: HashMap<NodeId, usize>,
+    lines: Vec<String>,
+}
+impl Writer<'_> {
+    // Line number of the node, children first. 0 for empty nodes
+    fn write(&mut self, node: NodeId) -> usize {
+        let (level, children, population) = self.life.node(node);
+        if population == 0 {
+            return 0;
+        }
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        let line = if level == 3 && !self.multi_state {
+            self.leaf(node)
+        } else if level == 1 {
+            let states = children.map(|child| self.life.node(child).1[0]);
+            format!("1 {} {} {} {}", states[0], states[1], states[2], states[3])
+        } else {
+            let refs = children.map(|child| self.write(child));
+            format!("{} {} {} {} {}", level, refs[0], refs[1], refs[2], refs[3])
+        };
+        self.lines.push(line);
+        self.ids.insert(node, self.lines.len());
+        self.lines.len()
+    }
+    fn leaf(&self, node: NodeId) -> String {
+        let mut rows = vec![String::new(); 8];
+        self.cells(node, 0, 0, &mut rows);
+        let mut line = String::new();
+        let last = rows.iter().rposition(|row| row.contains('*')).unwrap_or(0);
+        for row in rows.iter().take(last + 1) {
+            line += row.trim_end_matches('.');
+            line.push('$');
+        }
+        line
+    }
+    fn cells(&self, node: NodeId, x: usize, y: usize, rows: &mut [String]) {
+        let (level, children, _) = self.life.node(node);
+        if level == 0 {
+            let row = &mut rows[y];
+            while row.len() <= x {
+                row.push('.');
+            }
+            if children[0] != 0 {
+                row.replace_range(x..x + 1, "*");
+            }
+            return;
+        }
+        let half = 1 << (level - 1);
+        self.cells(children[0], x, y, rows);
+        self.cells(children[1], x + half, y, rows);
+        self.cells(children[2], x, y + half, rows);
+        self.cells(children[3], x + half, y + half, rows);
+    }
+}
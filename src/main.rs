@@ -1,18 +1,51 @@
-use crossterm::event::{Event, KeyCode, MouseEvent, MouseEventKind, MouseButton};
-use crossterm::terminal::SetSize;
-use crossterm::{Result, execute};
+use std::path::Path;
+use std::time::Duration;
+
+use crossterm::event::{Event, EventStream};
+use crossterm::Result;
+use futures::StreamExt;
 use gol::App;
+use tokio::time::{interval, MissedTickBehavior};
+
+// Screen is redrawn at most this often, however fast the simulation runs
+const FRAME_TIME: Duration = Duration::from_millis(33);
+const TICK_TIME: Duration = Duration::from_millis(5);
 
-fn main() -> Result<()> {
+#[tokio::main]
+async fn main() -> Result<()> {
     let mut app = App::new()?;
+    // Optional pattern file to start with, otherwise where the last run ended
+    if let Some(path) = std::env::args().nth(1) {
+        if let Err(err) = app.open(Path::new(&path)) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    } else {
+        app.restore_autosave();
+    }
     app.start().unwrap();
 
+    let mut events = EventStream::new();
+    let mut ticks = interval(TICK_TIME);
+    let mut frames = interval(FRAME_TIME);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    frames.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
     while app.run {
-        match crossterm::event::read().unwrap() {
-            Event::Key(k) => app.handle_key(k.code),
-            Event::Mouse(m) => app.handle_mouse(m),
-            Event::Resize(w, h) => app.handle_resize(w, h),
-            _ => {}
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(Event::Key(k))) => app.handle_key(k),
+                Some(Ok(Event::Mouse(m))) => app.handle_mouse(m),
+                Some(Ok(Event::Resize(w, h))) => app.handle_resize(w, h),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    app.exit();
+                    return Err(e);
+                }
+                None => app.exit(),
+            },
+            _ = ticks.tick() => app.tick(),
+            _ = frames.tick() => app.frame(),
         }
     }
 
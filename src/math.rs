@@ -1,6 +1,6 @@
 use std::cmp::{max, min};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Vec4<T: Sized> {
     pub x1: T,
     pub y1: T,
@@ -26,7 +26,7 @@ impl Vec4<i64> {
             x2: min(self.x2, r2.x2),
             y2: min(self.y2, r2.y2),
         };
-        if rect.x1 == rect.x2 || rect.y1 == rect.y2 {
+        if rect.x1 >= rect.x2 || rect.y1 >= rect.y2 {
             return None;
         }
         Some(rect)
@@ -35,13 +35,18 @@ impl Vec4<i64> {
         Vec4 { x1: 0, y1: 0, x2: 0, y2: 0 }
     }
 }
+impl Default for Vec4<i64> {
+    fn default() -> Self {
+        Vec4::new()
+    }
+}
 pub struct Vec2<T> {
     pub x: T,
     pub y: T,
 }
 impl<T> Vec2<T> {
     pub fn new(x: T, y: T) -> Vec2<T> {
-        Vec2 { x: x, y: y }
+        Vec2 { x, y }
     }
 }
 
@@ -0,0 +1,108 @@
+use crate::engine::LifeEngine;
+use crate::math::*;
+use crate::rule::Rule;
+
+// Cells cut out of a world or read from a file, independent of any engine
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Pattern {
+    pub width: i64,
+    pub height: i64,
+    // Cells with non zero state, relative to the top left corner
+    pub cells: Vec<(i64, i64, u8)>,
+    pub rule: Option<Rule>,
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+}
+impl Pattern {
+    pub fn new() -> Self {
+        Pattern::default()
+    }
+    // Copy a rectangle of the world
+    pub fn from_world(world: &dyn LifeEngine, rect: Vec4<i64>) -> Pattern {
+        let (data, _) = world.get_world(rect);
+        let size = rect.size();
+        let mut cells = Vec::new();
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let state = data[(x + y * size.x) as usize];
+                if state != 0 {
+                    cells.push((x, y, state));
+                }
+            }
+        }
+        Pattern {
+            width: size.x,
+            height: size.y,
+            cells,
+            rule: Some(world.rule()),
+            ..Pattern::new()
+        }
+    }
+    // Every live cell of an engine, moved so the bounding box starts at 0, 0
+    pub fn from_engine(world: &dyn LifeEngine) -> Pattern {
+        let mut pattern = Pattern {
+            rule: Some(world.rule()),
+            ..Pattern::new()
+        };
+        if let Some(rect) = world.bounding_box() {
+            pattern.width = rect.x2 - rect.x1;
+            pattern.height = rect.y2 - rect.y1;
+            pattern.cells = world
+                .live_cells()
+                .into_iter()
+                .map(|(x, y, state)| (x - rect.x1, y - rect.y1, state))
+                .collect();
+        }
+        pattern
+    }
+    // Set cells with the top left corner at x, y. Dead cells of the
+    // pattern leave the world as it is
+    pub fn place(&self, world: &mut dyn LifeEngine, x: i64, y: i64) {
+        for (cx, cy, state) in self.cells.iter() {
+            world.set_state(x + cx, y + cy, *state);
+        }
+    }
+    // Transforms keep the pattern at 0, 0 and its cells in row order
+    pub fn transpose(&mut self) {
+        self.remap(|x, y| (y, x));
+        std::mem::swap(&mut self.width, &mut self.height);
+    }
+    // Mirror left to right
+    pub fn flip_horizontal(&mut self) {
+        let width = self.width;
+        self.remap(|x, y| (width - 1 - x, y));
+    }
+    pub fn flip_vertical(&mut self) {
+        let height = self.height;
+        self.remap(|x, y| (x, height - 1 - y));
+    }
+    pub fn rotate_cw(&mut self) {
+        let height = self.height;
+        self.remap(|x, y| (height - 1 - y, x));
+        std::mem::swap(&mut self.width, &mut self.height);
+    }
+    pub fn rotate_ccw(&mut self) {
+        let width = self.width;
+        self.remap(|x, y| (y, width - 1 - x));
+        std::mem::swap(&mut self.width, &mut self.height);
+    }
+    pub fn rotate_180(&mut self) {
+        let (width, height) = (self.width, self.height);
+        self.remap(|x, y| (width - 1 - x, height - 1 - y));
+    }
+    fn remap(&mut self, f: impl Fn(i64, i64) -> (i64, i64)) {
+        for cell in self.cells.iter_mut() {
+            (cell.0, cell.1) = f(cell.0, cell.1);
+        }
+        self.cells.sort_by_key(|cell| (cell.1, cell.0));
+    }
+    pub fn get(&self, x: i64, y: i64) -> u8 {
+        self.cells
+            .iter()
+            .find(|cell| cell.0 == x && cell.1 == y)
+            .map_or(0, |cell| cell.2)
+    }
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+}
@@ -0,0 +1,53 @@
+use crate::pattern::Pattern;
+
+// Plaintext .cells files, '!' comment lines then rows of '.' and 'O'
+pub fn read_cells(text: &str) -> Result<Pattern, String> {
+    let mut pattern = Pattern::new();
+    let mut y = 0;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if let Some(comment) = line.strip_prefix('!') {
+            match comment.strip_prefix("Name:") {
+                Some(name) => pattern.name = Some(name.trim().to_string()),
+                None => pattern.comments.push(comment.trim().to_string()),
+            }
+            continue;
+        }
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                'O' | '*' => pattern.cells.push((x as i64, y, 1)),
+                c => return Err(format!("unexpected '{}' in plaintext pattern", c)),
+            }
+        }
+        pattern.width = pattern.width.max(line.len() as i64);
+        y += 1;
+    }
+    // Trailing empty lines are not part of the pattern
+    pattern.height = pattern.cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0);
+    Ok(pattern)
+}
+
+// Decay states can't be told apart in plaintext and are written as alive
+pub fn write_cells(pattern: &Pattern) -> String {
+    let mut text = String::new();
+    if let Some(name) = &pattern.name {
+        text += &format!("!Name: {}\n", name);
+    }
+    for comment in pattern.comments.iter() {
+        text += &format!("!{}\n", comment);
+    }
+    let mut rows = vec![Vec::new(); pattern.height as usize];
+    for (x, y, _) in pattern.cells.iter() {
+        rows[*y as usize].push(*x as usize);
+    }
+    for row in rows {
+        let mut line = vec!['.'; row.iter().max().map_or(0, |x| x + 1)];
+        for x in row {
+            line[x] = 'O';
+        }
+        text.extend(line);
+        text.push('\n');
+    }
+    text
+}
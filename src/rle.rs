@@ -0,0 +1,163 @@
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+// Lines of written patterns are kept shorter than this
+const LINE_LENGTH: usize = 70;
+
+// Read Run Length Encoded pattern, optionally with '#' comment lines and
+// an 'x = .., y = .., rule = ..' header
+pub fn read_rle(text: &str) -> Result<Pattern, String> {
+    let mut pattern = Pattern::new();
+    let mut lines = text.lines().map(str::trim).peekable();
+    // Comments and header
+    while let Some(line) = lines.peek() {
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut chars = comment.chars();
+            let kind = chars.next();
+            let text = chars.as_str().trim().to_string();
+            match kind {
+                Some('N') => pattern.name = Some(text),
+                Some('C' | 'c') => pattern.comments.push(text),
+                _ => {}
+            }
+        } else if line.starts_with('x') {
+            read_header(line, &mut pattern)?;
+        } else if !line.is_empty() {
+            break;
+        }
+        lines.next();
+    }
+    // Cells
+    let (mut x, mut y) = (0i64, 0i64);
+    let mut count = None;
+    let mut prefix = None;
+    'lines: for line in lines {
+        for c in line.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                count = Some(count.unwrap_or(0i64).saturating_mul(10).saturating_add(digit as i64));
+                continue;
+            }
+            // High Generations states are a prefix letter and a state letter
+            if ('p'..='y').contains(&c) && prefix.is_none() {
+                prefix = Some(c as u32 - 'p' as u32 + 1);
+                continue;
+            }
+            if prefix.is_some() && !c.is_ascii_uppercase() {
+                return Err(format!("expected state letter after prefix, found '{}'", c));
+            }
+            let n = count.take().unwrap_or(1);
+            let state = match c {
+                'b' | '.' => 0,
+                'o' => 1,
+                'A'..='X' => {
+                    let high = prefix.take().map_or(0, |p: u32| p * 24);
+                    let state = high + c as u32 - 'A' as u32 + 1;
+                    u8::try_from(state).map_err(|_| format!("state {} is too high", state))?
+                }
+                '$' => {
+                    x = 0;
+                    y += n;
+                    continue;
+                }
+                '!' => break 'lines,
+                c if c.is_whitespace() => continue,
+                c => return Err(format!("unexpected '{}' in pattern", c)),
+            };
+            if state != 0 {
+                for i in 0..n {
+                    pattern.cells.push((x + i, y, state));
+                }
+                pattern.width = pattern.width.max(x + n);
+                pattern.height = pattern.height.max(y + 1);
+            }
+            x += n;
+        }
+    }
+    Ok(pattern)
+}
+
+fn read_header(line: &str, pattern: &mut Pattern) -> Result<(), String> {
+    for field in line.split(',') {
+        let (key, value) = field
+            .split_once('=')
+            .ok_or(format!("invalid header field '{}'", field.trim()))?;
+        let value = value.trim();
+        match key.trim() {
+            "x" => pattern.width = value.parse().map_err(|_| format!("invalid width '{}'", value))?,
+            "y" => pattern.height = value.parse().map_err(|_| format!("invalid height '{}'", value))?,
+            // Bounded grid suffix like ':T100,100' is not supported and dropped
+            "rule" => pattern.rule = Some(Rule::parse(value.split(':').next().unwrap())?),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn write_rle(pattern: &Pattern) -> String {
+    let mut text = String::new();
+    if let Some(name) = &pattern.name {
+        text += &format!("#N {}\n", name);
+    }
+    for comment in pattern.comments.iter() {
+        text += &format!("#C {}\n", comment);
+    }
+    text += &format!("x = {}, y = {}", pattern.width, pattern.height);
+    if let Some(rule) = &pattern.rule {
+        text += &format!(", rule = {}", rule);
+    }
+    text.push('\n');
+    // Two state patterns use b and o, others . and letters
+    let multi_state = pattern.cells.iter().any(|cell| cell.2 > 1)
+        || pattern.rule.is_some_and(|rule| rule.states() > 2);
+    let mut cells = pattern.cells.clone();
+    cells.sort_by_key(|(x, y, _)| (*y, *x));
+    let mut runs: Vec<(i64, String)> = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    for (cx, cy, state) in cells {
+        if cy > y {
+            runs.push((cy - y, "$".to_string()));
+            x = 0;
+            y = cy;
+        }
+        if cx > x {
+            runs.push((cx - x, if multi_state { "." } else { "b" }.to_string()));
+        }
+        let tag = match (multi_state, state) {
+            (false, _) => "o".to_string(),
+            (true, state) => state_letters(state),
+        };
+        match runs.last_mut() {
+            Some(run) if run.1 == tag && cx == x => run.0 += 1,
+            _ => runs.push((1, tag)),
+        }
+        x = cx + 1;
+    }
+    runs.push((1, "!".to_string()));
+    // Wrap without splitting runs
+    let mut line = String::new();
+    for (n, tag) in runs {
+        let run = match n {
+            1 => tag,
+            n => format!("{}{}", n, tag),
+        };
+        if line.len() + run.len() > LINE_LENGTH {
+            text += &line;
+            text.push('\n');
+            line.clear();
+        }
+        line += &run;
+    }
+    text += &line;
+    text.push('\n');
+    text
+}
+
+// Letters of a Generations state, 1 is A and 25 is pA
+fn state_letters(state: u8) -> String {
+    let state = state as u32 - 1;
+    let letter = char::from_u32('A' as u32 + state % 24).unwrap();
+    match state / 24 {
+        0 => letter.to_string(),
+        high => format!("{}{}", char::from_u32('p' as u32 + high - 1).unwrap(), letter),
+    }
+}
@@ -0,0 +1,305 @@
+use std::{fmt, str::FromStr};
+
+use crate::math::*;
+
+pub const RULE_PRESETS: [(&str, &str); 14] = [
+    ("Life", "B3/S23"),
+    ("HighLife", "B36/S23"),
+    ("Seeds", "B2/S"),
+    ("Day & Night", "B3678/S34678"),
+    ("Maze", "B3/S12345"),
+    ("Replicator", "B1357/S1357"),
+    ("2x2", "B36/S125"),
+    ("Life without Death", "B3/S012345678"),
+    ("Morley", "B368/S245"),
+    ("Diamoeba", "B35678/S5678"),
+    ("tlife", "B3/S2-i34q"),
+    ("Brian's Brain", "B2/S/C3"),
+    ("Star Wars", "B2/S345/C4"),
+    ("Frogs", "B34/S12/C3"),
+];
+
+const N: (i64, i64) = (0, -1);
+const NE: (i64, i64) = (1, -1);
+const E: (i64, i64) = (1, 0);
+const SE: (i64, i64) = (1, 1);
+const S: (i64, i64) = (0, 1);
+const SW: (i64, i64) = (-1, 1);
+const W: (i64, i64) = (-1, 0);
+const NW: (i64, i64) = (-1, -1);
+
+type Configurations = &'static [(char, &'static [(i64, i64)])];
+
+// Hensel letters for 1 to 4 neighbours with one configuration of each,
+// 5 to 7 neighbours use inverted configurations of 3 to 1
+const HENSEL: [Configurations; 4] = [
+    &[('c', &[NE]), ('e', &[N])],
+    &[
+        ('c', &[NE, SE]),
+        ('e', &[N, E]),
+        ('k', &[N, SE]),
+        ('a', &[N, NE]),
+        ('i', &[N, S]),
+        ('n', &[NE, SW]),
+    ],
+    &[
+        ('c', &[NE, SE, SW]),
+        ('e', &[N, E, S]),
+        ('k', &[N, E, SW]),
+        ('a', &[N, NE, E]),
+        ('i', &[NE, E, SE]),
+        ('n', &[N, NE, SE]),
+        ('y', &[N, SE, SW]),
+        ('q', &[N, NE, SW]),
+        ('j', &[N, NE, W]),
+        ('r', &[N, NE, S]),
+    ],
+    &[
+        ('c', &[NE, SE, SW, NW]),
+        ('e', &[N, E, S, W]),
+        ('k', &[N, NE, SE, W]),
+        ('a', &[N, NE, E, SE]),
+        ('i', &[N, NE, SE, S]),
+        ('n', &[N, NE, SE, NW]),
+        ('y', &[N, NE, SE, SW]),
+        ('q', &[N, NE, E, SW]),
+        ('j', &[N, NE, S, W]),
+        ('r', &[N, NE, E, S]),
+        ('t', &[N, NE, S, NW]),
+        ('w', &[N, NE, SW, W]),
+        ('z', &[N, NE, S, SW]),
+    ],
+];
+
+fn letters(count: usize) -> Configurations {
+    match count {
+        1..=4 => HENSEL[count - 1],
+        5..=7 => HENSEL[8 - count - 1],
+        _ => &[],
+    }
+}
+
+// All rotations and reflections of a neighbourhood configuration
+fn symmetries(count: usize, letter: char) -> Vec<u8> {
+    let config = letters(count).iter().find(|l| l.0 == letter).unwrap().1;
+    let mut mask = 0u8;
+    for dir in config.iter() {
+        mask |= 1 << dir2index(dir.0, dir.1);
+    }
+    if count > 4 {
+        mask = !mask;
+    }
+    let mut masks = Vec::new();
+    for mirror in [false, true] {
+        for rotation in 0..4 {
+            let mut out = 0u8;
+            for i in 0..8 {
+                if mask >> i & 1 == 0 {
+                    continue;
+                }
+                let (mut x, mut y) = index2dir(i);
+                if mirror {
+                    x = -x;
+                }
+                for _ in 0..rotation {
+                    (x, y) = (-y, x);
+                }
+                out |= 1 << dir2index(x as i64, y as i64);
+            }
+            masks.push(out);
+        }
+    }
+    masks
+}
+
+// Transition table indexed by bitmask of living neighbours, bit n of the
+// mask is the neighbour in direction index2dir(n). State 0 is dead, 1 alive
+// and the rest are decay states of Generations rules
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rule {
+    birth: [u64; 4],
+    survival: [u64; 4],
+    states: u8,
+}
+impl Rule {
+    pub fn life() -> Rule {
+        Rule::parse("B3/S23").unwrap()
+    }
+    pub fn parse(s: &str) -> Result<Rule, String> {
+        let parts: Vec<&str> = s.trim().split('/').collect();
+        if parts.len() != 2 && parts.len() != 3 {
+            return Err(format!("'{}' is not a B/S or B/S/C rulestring", s));
+        }
+        // Split to birth, survival and states part
+        let mut birth = None;
+        let mut survival = None;
+        let mut states = None;
+        let mut plain = Vec::new();
+        for part in parts {
+            match part.chars().next() {
+                Some('B' | 'b') => birth = Some(&part[1..]),
+                Some('S' | 's') => survival = Some(&part[1..]),
+                Some('C' | 'c' | 'G' | 'g') => states = Some(&part[1..]),
+                _ => plain.push(part),
+            }
+        }
+        // Parts without letter follow S/B/C order
+        for part in plain {
+            if survival.is_none() {
+                survival = Some(part);
+            } else if birth.is_none() {
+                birth = Some(part);
+            } else if states.is_none() {
+                states = Some(part);
+            } else {
+                return Err(format!("'{}' is not a B/S or B/S/C rulestring", s));
+            }
+        }
+        let states = match states {
+            Some(states) => match states.parse::<u8>() {
+                Ok(n) if n >= 2 => n,
+                _ => return Err(format!("invalid number of states '{}'", states)),
+            },
+            None => 2,
+        };
+        let rule = Rule {
+            birth: Rule::parse_table(birth.unwrap_or(""))?,
+            survival: Rule::parse_table(survival.unwrap_or(""))?,
+            states,
+        };
+        if rule.birth[0] & 1 == 1 {
+            return Err("B0 rules are not supported".to_string());
+        }
+        Ok(rule)
+    }
+    fn parse_table(s: &str) -> Result<[u64; 4], String> {
+        let mut table = [0u64; 4];
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            let count = match c.to_digit(10) {
+                Some(n) if n <= 8 => n as usize,
+                _ => return Err(format!("invalid neighbour count '{}'", c)),
+            };
+            // Optional Hensel letters
+            let invert = chars.next_if_eq(&'-').is_some();
+            let mut chosen = Vec::new();
+            while let Some(letter) = chars.next_if(|c| c.is_ascii_lowercase()) {
+                if !letters(count).iter().any(|l| l.0 == letter) {
+                    return Err(format!("invalid configuration '{}{}'", count, letter));
+                }
+                chosen.push(letter);
+            }
+            if invert && chosen.is_empty() {
+                return Err(format!("missing configurations after '{}-'", count));
+            }
+            // Mark configurations
+            let mut masks = Vec::new();
+            if chosen.is_empty() {
+                masks.extend((0..=255u8).filter(|m| m.count_ones() as usize == count));
+            } else {
+                for (letter, _) in letters(count) {
+                    if chosen.contains(letter) != invert {
+                        masks.extend(symmetries(count, *letter));
+                    }
+                }
+            }
+            for mask in masks {
+                table[mask as usize / 64] |= 1 << (mask % 64);
+            }
+        }
+        Ok(table)
+    }
+    fn lookup(table: &[u64; 4], mask: u8) -> bool {
+        table[mask as usize / 64] >> (mask % 64) & 1 == 1
+    }
+    // Birth and survival neighbour counts when only the count matters
+    pub fn counts(&self) -> Option<(u16, u16)> {
+        let counts = |table: &[u64; 4]| -> Option<u16> {
+            let mut counts = 0u16;
+            for count in 0..=8 {
+                let mut masks = (0..=255u8).filter(|m| m.count_ones() == count);
+                let first = Rule::lookup(table, masks.next().unwrap());
+                if masks.any(|m| Rule::lookup(table, m) != first) {
+                    return None;
+                }
+                counts |= (first as u16) << count;
+            }
+            Some(counts)
+        };
+        Some((counts(&self.birth)?, counts(&self.survival)?))
+    }
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+    pub fn next(&self, state: u8, neighbours: u8) -> u8 {
+        match state {
+            0 => Rule::lookup(&self.birth, neighbours) as u8,
+            1 if Rule::lookup(&self.survival, neighbours) => 1,
+            // Dying cells decay until they reach the last state
+            _ if state + 1 < self.states => state + 1,
+            _ => 0,
+        }
+    }
+    pub fn name(&self) -> Option<&'static str> {
+        RULE_PRESETS
+            .iter()
+            .find(|preset| Rule::parse(preset.1).as_ref() == Ok(self))
+            .map(|preset| preset.0)
+    }
+    fn table_str(table: &[u64; 4]) -> String {
+        let mut out = String::new();
+        for count in 0..=8 {
+            let all = letters(count);
+            if all.is_empty() {
+                let mask = (0..=255u8).find(|m| m.count_ones() as usize == count).unwrap();
+                if Rule::lookup(table, mask) {
+                    out.push_str(&count.to_string());
+                }
+                continue;
+            }
+            let (on, off): (Vec<char>, Vec<char>) = all
+                .iter()
+                .map(|l| l.0)
+                .partition(|l| Rule::lookup(table, symmetries(count, *l)[0]));
+            if on.is_empty() {
+                continue;
+            }
+            out.push_str(&count.to_string());
+            if off.is_empty() {
+                continue;
+            }
+            if on.len() <= off.len() {
+                out.extend(on);
+            } else {
+                out.push('-');
+                out.extend(off);
+            }
+        }
+        out
+    }
+}
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::life()
+    }
+}
+impl FromStr for Rule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::parse(s)
+    }
+}
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "B{}/S{}",
+            Rule::table_str(&self.birth),
+            Rule::table_str(&self.survival)
+        )?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use crate::engine::LifeEngine;
+use crate::math::*;
+use crate::pattern::Pattern;
+
+// Rectangle covering two corner cells, in any order
+pub fn span(a: (i64, i64), b: (i64, i64)) -> Vec4<i64> {
+    Vec4 {
+        x1: a.0.min(b.0),
+        y1: a.1.min(b.1),
+        x2: a.0.max(b.0) + 1,
+        y2: a.1.max(b.1) + 1,
+    }
+}
+
+// Kill every cell of the rectangle
+pub fn clear_rect(world: &mut dyn LifeEngine, rect: Vec4<i64>) {
+    let pattern = Pattern::from_world(world, rect);
+    for (x, y, _) in pattern.cells {
+        world.set_state(rect.x1 + x, rect.y1 + y, 0);
+    }
+}
+
+pub fn fill_rect(world: &mut dyn LifeEngine, rect: Vec4<i64>, state: u8) {
+    for y in rect.y1..rect.y2 {
+        for x in rect.x1..rect.x2 {
+            world.set_state(x, y, state);
+        }
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// Escape sequence asking the terminal to put text on the system
+// clipboard, works over ssh as long as the terminal allows it
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
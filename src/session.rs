@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use crate::engine::{engine_by_name, transfer, LifeEngine};
+use crate::macrocell::*;
+use crate::math::*;
+
+// Everything needed to continue where the app was left. Cells, rule and
+// generation are kept as a macrocell after the settings
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Session {
+    pub engine: String,
+    pub window: Vec4<i64>,
+    pub step_exp: u8,
+    pub speed: u32,
+    pub playing: bool,
+}
+impl Session {
+    pub fn write(&self, world: &dyn LifeEngine) -> String {
+        let w = &self.window;
+        let mut text = String::from("gol session\n");
+        text += &format!("engine {}\n", self.engine);
+        text += &format!("window {} {} {} {}\n", w.x1, w.y1, w.x2, w.y2);
+        text += &format!("step_exp {}\n", self.step_exp);
+        text += &format!("speed {}\n", self.speed);
+        text += &format!("playing {}\n", self.playing);
+        text + &write_macrocell(world)
+    }
+    pub fn read(text: &str) -> Result<(Session, Box<dyn LifeEngine>), String> {
+        let (settings, cells) = match text.find("[M2]") {
+            Some(i) => text.split_at(i),
+            None => return Err("session has no cells".to_string()),
+        };
+        let mut lines = settings.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some("gol session") {
+            return Err("not a session file".to_string());
+        }
+        let mut session = Session {
+            engine: String::new(),
+            window: Vec4::new(),
+            step_exp: 0,
+            speed: 1,
+            playing: false,
+        };
+        for line in lines {
+            let (key, value) = line.split_once(' ').ok_or(format!("invalid setting '{}'", line))?;
+            let invalid = format!("invalid {} '{}'", key, value);
+            match key {
+                "engine" => session.engine = value.to_string(),
+                "window" => {
+                    let numbers = value
+                        .split_whitespace()
+                        .map(|n| n.parse::<i64>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| invalid.clone())?;
+                    match numbers.as_slice() {
+                        [x1, y1, x2, y2] => session.window = Vec4 { x1: *x1, y1: *y1, x2: *x2, y2: *y2 },
+                        _ => return Err(invalid),
+                    }
+                }
+                "step_exp" => session.step_exp = value.parse().map_err(|_| invalid.clone())?,
+                "speed" => session.speed = value.parse().map_err(|_| invalid.clone())?,
+                "playing" => session.playing = value.parse().map_err(|_| invalid.clone())?,
+                // Unknown settings of newer versions
+                _ => {}
+            }
+        }
+        let life = read_macrocell(cells)?;
+        let world: Box<dyn LifeEngine> = match engine_by_name(&session.engine) {
+            Some(_) if session.engine == "HASHLIFE" => Box::new(life),
+            Some(mut world) => {
+                transfer(&life, world.as_mut());
+                world
+            }
+            None => return Err(format!("unknown engine '{}'", session.engine)),
+        };
+        Ok((session, world))
+    }
+}
+
+// Files in the home directory, or the working directory without one
+fn home_file(name: &str) -> PathBuf {
+    let dir = std::env::var_os("HOME").map_or(PathBuf::from("."), PathBuf::from);
+    dir.join(name)
+}
+// Session saved and restored by hand
+pub fn session_path() -> PathBuf {
+    home_file(".gol_session")
+}
+// Session written on exit, kept apart so it never replaces a saved one
+pub fn autosave_path() -> PathBuf {
+    home_file(".gol_autosave")
+}
@@ -0,0 +1,62 @@
+use crate::engine::LifeEngine;
+use crate::math::*;
+use crate::pattern::Pattern;
+use crate::selection::clear_rect;
+
+// Side of the soups apgsearch starts from
+pub const SOUP_SIZE: i64 = 16;
+
+// SplitMix64, the same seed gives the same soup on every platform
+pub struct SoupRng(u64);
+impl SoupRng {
+    pub fn new(seed: u64) -> Self {
+        SoupRng(seed)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    // Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// Seed for soups asked for without one
+pub fn random_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64);
+    SoupRng::new(nanos).next_u64()
+}
+
+// Cells drawn row by row, each alive with the given probability
+pub fn soup(width: i64, height: i64, density: f64, seed: u64) -> Pattern {
+    let mut rng = SoupRng::new(seed);
+    let mut cells = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if rng.next_f64() < density {
+                cells.push((x, y, 1));
+            }
+        }
+    }
+    Pattern {
+        width,
+        height,
+        cells,
+        name: Some(format!("soup {} density {}", seed, density)),
+        ..Pattern::new()
+    }
+}
+
+// Replace a rectangle of any engine with a soup, the seed regenerates it
+pub fn place_soup(world: &mut dyn LifeEngine, rect: Vec4<i64>, density: f64, seed: u64) {
+    let size = rect.size();
+    let pattern = soup(size.x, size.y, density, seed);
+    clear_rect(world, rect);
+    pattern.place(world, rect.x1, rect.y1);
+}
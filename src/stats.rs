@@ -0,0 +1,55 @@
+use std::collections::VecDeque;
+
+// Generations the population is remembered for
+pub const POPULATION_HISTORY: usize = 512;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Cells that came alive and died in one step, any non zero state
+// counts as alive
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct StepStats {
+    pub births: u64,
+    pub deaths: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub last_step: StepStats,
+    // Generation and population after each recent step, oldest first
+    pub history: VecDeque<(u64, u64)>,
+}
+impl Stats {
+    pub fn new() -> Self {
+        Stats::default()
+    }
+    // Generations at or after this one are dropped first, they belong to
+    // a future that was undone or rewound
+    pub fn record(&mut self, generation: u64, population: u64, step: StepStats) {
+        while self.history.back().is_some_and(|last| last.0 >= generation) {
+            self.history.pop_back();
+        }
+        if self.history.len() == POPULATION_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back((generation, population));
+        self.last_step = step;
+    }
+    pub fn populations(&self) -> impl Iterator<Item = u64> + '_ {
+        self.history.iter().map(|entry| entry.1)
+    }
+}
+
+// Last width values as block characters scaled between their minimum
+// and maximum
+pub fn sparkline(values: &[u64], width: usize) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let min = values.iter().copied().min().unwrap_or(0);
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|value| match max - min {
+            0 => SPARKS[0],
+            range => SPARKS[((value - min) * (SPARKS.len() as u64 - 1) / range) as usize],
+        })
+        .collect()
+}
@@ -0,0 +1,176 @@
+use std::collections::VecDeque;
+
+use crate::engine::{bounding_box, engine_by_name, transfer, LifeEngine};
+use crate::hashlife::{HashLife, NodeId, NODE_BYTES};
+use crate::pattern::Pattern;
+use crate::rle::*;
+use crate::rule::Rule;
+
+// Nodes are rebuilt from the recorded roots once unreachable ones outnumber
+// them, and never below this many
+const COMPACT_NODES: usize = 1 << 12;
+
+enum Cells {
+    // Run length encoded relative to origin
+    Rle { origin: (i64, i64), rle: String },
+    // Root in the timeline's own quadtree with its top left corner, frames
+    // share every node they have in common
+    Tree { origin: (i64, i64), root: NodeId },
+}
+
+// Live cells and rule of one generation
+struct Frame {
+    generation: u64,
+    rule: Rule,
+    cells: Cells,
+    // Text or nodes this frame added
+    bytes: usize,
+}
+
+// Last generations of a world, oldest are dropped once full
+pub struct Timeline {
+    frames: VecDeque<Frame>,
+    capacity: usize,
+    max_bytes: usize,
+    bytes: usize,
+    nodes: HashLife,
+}
+impl Timeline {
+    pub fn new(capacity: usize, max_bytes: usize) -> Self {
+        Timeline {
+            frames: VecDeque::new(),
+            capacity: capacity.max(1),
+            max_bytes,
+            bytes: 0,
+            nodes: HashLife::new(),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.bytes = 0;
+        self.nodes = HashLife::new();
+    }
+    pub fn generation(&self, index: usize) -> Option<u64> {
+        self.frames.get(index).map(|frame| frame.generation)
+    }
+    // Index of the frame recorded for a generation
+    pub fn position(&self, generation: u64) -> Option<usize> {
+        self.frames.iter().position(|frame| frame.generation == generation)
+    }
+    // Record current generation. Frames from this generation on belong to
+    // another history once the world went back, they are dropped. The
+    // newest frame is kept even when it alone is over the byte limit
+    pub fn record(&mut self, world: &dyn LifeEngine) {
+        let generation = world.generation();
+        while self.frames.back().is_some_and(|frame| frame.generation >= generation) {
+            self.drop_frame(false);
+        }
+        let frame = match world.quadtree() {
+            Some(life) => self.tree_frame(life),
+            None => rle_frame(world),
+        };
+        self.bytes += frame.bytes;
+        self.frames.push_back(frame);
+        while self.frames.len() > self.capacity || (self.frames.len() > 1 && self.bytes > self.max_bytes) {
+            self.drop_frame(true);
+        }
+        if self.nodes.node_count() > COMPACT_NODES.max(2 * self.tree_bytes() / NODE_BYTES) {
+            self.compact();
+        }
+    }
+    fn drop_frame(&mut self, oldest: bool) {
+        let frame = if oldest { self.frames.pop_front() } else { self.frames.pop_back() };
+        if let Some(frame) = frame {
+            self.bytes -= frame.bytes;
+        }
+    }
+    fn tree_frame(&mut self, life: &HashLife) -> Frame {
+        let nodes = self.nodes.node_count();
+        let root = self.nodes.import(life, life.root());
+        let size = life.size();
+        Frame {
+            generation: life.generation(),
+            rule: life.rule(),
+            cells: Cells::Tree { origin: (size.x1, size.y1), root },
+            bytes: (self.nodes.node_count() - nodes) * NODE_BYTES,
+        }
+    }
+    fn tree_bytes(&self) -> usize {
+        let tree = |frame: &&Frame| matches!(frame.cells, Cells::Tree { .. });
+        self.frames.iter().filter(tree).map(|frame| frame.bytes).sum()
+    }
+    // Copy the roots still recorded into a fresh quadtree, dropping nodes
+    // only dropped frames used
+    fn compact(&mut self) {
+        let old = std::mem::take(&mut self.nodes);
+        for frame in self.frames.iter_mut() {
+            if let Cells::Tree { root, .. } = &mut frame.cells {
+                let nodes = self.nodes.node_count();
+                *root = self.nodes.import(&old, *root);
+                self.bytes -= frame.bytes;
+                frame.bytes = (self.nodes.node_count() - nodes) * NODE_BYTES;
+                self.bytes += frame.bytes;
+            }
+        }
+    }
+    // Fresh engine of the same kind at a recorded generation and its rule
+    pub fn restore(&self, index: usize, world: &dyn LifeEngine) -> Option<Box<dyn LifeEngine>> {
+        let frame = self.frames.get(index)?;
+        let mut restored = engine_by_name(world.name())?;
+        match &frame.cells {
+            Cells::Rle { origin, rle } => {
+                restored.set_rule(frame.rule);
+                if !rle.is_empty() {
+                    let pattern = read_rle(rle).ok()?;
+                    pattern.place(restored.as_mut(), origin.0, origin.1);
+                }
+            }
+            Cells::Tree { origin, root } => {
+                let mut life = HashLife::new();
+                let root = life.import(&self.nodes, *root);
+                life.set_root_at(root, *origin);
+                life.set_rule(frame.rule);
+                if restored.quadtree().is_some() {
+                    restored = Box::new(life);
+                } else {
+                    transfer(&life, restored.as_mut());
+                }
+            }
+        }
+        restored.set_generation(frame.generation);
+        Some(restored)
+    }
+    // Bytes used by recorded frames, not counting nodes left over from
+    // dropped ones until the next compaction
+    pub fn memory(&self) -> usize {
+        self.bytes
+    }
+}
+
+fn rle_frame(world: &dyn LifeEngine) -> Frame {
+    let cells = world.live_cells();
+    let (origin, rle) = match bounding_box(&cells) {
+        Some(rect) => {
+            let pattern = Pattern {
+                width: rect.x2 - rect.x1,
+                height: rect.y2 - rect.y1,
+                cells: cells.iter().map(|(x, y, state)| (x - rect.x1, y - rect.y1, *state)).collect(),
+                ..Pattern::new()
+            };
+            ((rect.x1, rect.y1), write_rle(&pattern))
+        }
+        None => ((0, 0), String::new()),
+    };
+    Frame {
+        generation: world.generation(),
+        rule: world.rule(),
+        bytes: rle.len(),
+        cells: Cells::Rle { origin, rle },
+    }
+}
@@ -0,0 +1,158 @@
+// Shapes for drawing into a world, as lists of cell coordinates
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tool {
+    // Free hand with the brush
+    Pen,
+    Line,
+    Rectangle,
+    FilledRectangle,
+    Ellipse,
+    FilledEllipse,
+}
+impl Tool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Pen => "PEN",
+            Tool::Line => "LINE",
+            Tool::Rectangle => "RECTANGLE",
+            Tool::FilledRectangle => "FILLED RECTANGLE",
+            Tool::Ellipse => "ELLIPSE",
+            Tool::FilledEllipse => "FILLED ELLIPSE",
+        }
+    }
+    pub fn next(&self) -> Tool {
+        match self {
+            Tool::Pen => Tool::Line,
+            Tool::Line => Tool::Rectangle,
+            Tool::Rectangle => Tool::FilledRectangle,
+            Tool::FilledRectangle => Tool::Ellipse,
+            Tool::Ellipse => Tool::FilledEllipse,
+            Tool::FilledEllipse => Tool::Pen,
+        }
+    }
+    // Cells of the shape dragged from one corner or end to the other.
+    // Pen has no shape of its own
+    pub fn shape(&self, from: (i64, i64), to: (i64, i64)) -> Vec<(i64, i64)> {
+        match self {
+            Tool::Pen => vec![to],
+            Tool::Line => line(from, to),
+            Tool::Rectangle => rectangle(from, to, false),
+            Tool::FilledRectangle => rectangle(from, to, true),
+            Tool::Ellipse => ellipse(from, to, false),
+            Tool::FilledEllipse => ellipse(from, to, true),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BrushShape {
+    Square,
+    Round,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Brush {
+    pub shape: BrushShape,
+    // Width in cells
+    pub size: u8,
+}
+impl Brush {
+    pub fn new() -> Self {
+        Brush { shape: BrushShape::Square, size: 1 }
+    }
+    // Cells covered with the brush centred on a cell
+    pub fn stamp(&self, (x, y): (i64, i64)) -> Vec<(i64, i64)> {
+        let size = self.size.max(1) as i64;
+        let from = (x - (size - 1) / 2, y - (size - 1) / 2);
+        let to = (from.0 + size - 1, from.1 + size - 1);
+        match self.shape {
+            BrushShape::Square => rectangle(from, to, true),
+            BrushShape::Round => ellipse(from, to, true),
+        }
+    }
+    // Brush dragged along a shape, every cell once
+    pub fn paint(&self, cells: &[(i64, i64)]) -> Vec<(i64, i64)> {
+        if self.size <= 1 {
+            return cells.to_vec();
+        }
+        let mut painted: Vec<(i64, i64)> = cells.iter().flat_map(|cell| self.stamp(*cell)).collect();
+        painted.sort_unstable();
+        painted.dedup();
+        painted
+    }
+}
+impl Default for Brush {
+    fn default() -> Self {
+        Brush::new()
+    }
+}
+
+// Bresenham line, both ends included
+pub fn line(from: (i64, i64), to: (i64, i64)) -> Vec<(i64, i64)> {
+    let dx = (to.0 - from.0).abs();
+    let dy = -(to.1 - from.1).abs();
+    let sx = if from.0 < to.0 { 1 } else { -1 };
+    let sy = if from.1 < to.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = from;
+    let mut cells = Vec::with_capacity(dx.max(-dy) as usize + 1);
+    loop {
+        cells.push((x, y));
+        if (x, y) == to {
+            return cells;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+// Rectangle between two corners, both included
+pub fn rectangle(a: (i64, i64), b: (i64, i64), filled: bool) -> Vec<(i64, i64)> {
+    let (x1, x2) = (a.0.min(b.0), a.0.max(b.0));
+    let (y1, y2) = (a.1.min(b.1), a.1.max(b.1));
+    let mut cells = Vec::new();
+    for y in y1..=y2 {
+        for x in x1..=x2 {
+            if filled || x == x1 || x == x2 || y == y1 || y == y2 {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+// Ellipse inside the rectangle between two corners. Hollow ellipses are
+// the cells of the filled one next to a cell outside of it
+pub fn ellipse(a: (i64, i64), b: (i64, i64), filled: bool) -> Vec<(i64, i64)> {
+    let (x1, x2) = (a.0.min(b.0), a.0.max(b.0));
+    let (y1, y2) = (a.1.min(b.1), a.1.max(b.1));
+    // Centre and radii in half cells so even sizes stay symmetric
+    let (cx, cy) = (x1 + x2, y1 + y2);
+    let (rx, ry) = ((x2 - x1 + 1) as f64, (y2 - y1 + 1) as f64);
+    let inside = |x: i64, y: i64| {
+        let nx = (2 * x - cx) as f64 / rx;
+        let ny = (2 * y - cy) as f64 / ry;
+        x >= x1 && x <= x2 && y >= y1 && y <= y2 && nx * nx + ny * ny <= 1.0
+    };
+    let mut cells = Vec::new();
+    for y in y1..=y2 {
+        for x in x1..=x2 {
+            if !inside(x, y) {
+                continue;
+            }
+            let edge = !inside(x - 1, y) || !inside(x + 1, y) || !inside(x, y - 1) || !inside(x, y + 1);
+            if filled || edge {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
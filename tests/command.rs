@@ -0,0 +1,20 @@
+use gol::{Command, Rule};
+
+#[test]
+fn parse_commands() {
+    assert_eq!(Command::parse("step 1000"), Ok(Command::Step(1000)));
+    assert_eq!(Command::parse("  goto 50_000 "), Ok(Command::Goto(50000)));
+    assert_eq!(Command::parse("s 5"), Ok(Command::Step(5)));
+    assert_eq!(Command::parse("g 7"), Ok(Command::Goto(7)));
+    assert_eq!(Command::parse("rule B36/S23"), Ok(Command::Rule(Rule::parse("B36/S23").unwrap())));
+    assert_eq!(Command::parse("soup"), Ok(Command::Soup { seed: None, density: 50 }));
+    assert_eq!(Command::parse("soup 1_234"), Ok(Command::Soup { seed: Some(1234), density: 50 }));
+    assert_eq!(Command::parse("soup 7 35%"), Ok(Command::Soup { seed: Some(7), density: 35 }));
+}
+
+#[test]
+fn invalid_commands() {
+    for text in ["", "step", "step -1", "step ten", "goto 1 2", "jump 5", "rule", "rule B3/X", "soup x", "soup 1 101", "soup 1 50 2"] {
+        assert!(Command::parse(text).is_err(), "{:?}", text);
+    }
+}
@@ -0,0 +1,231 @@
+use gol::{next_engine, transfer, DenseWorld, HashLife, LifeEngine, Rule, World};
+
+// Deterministic xorshift so failures can be reproduced
+struct Rng(u64);
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn engines() -> Vec<Box<dyn LifeEngine>> {
+    vec![
+        Box::new(DenseWorld::new()),
+        Box::new(World::new()),
+        Box::new(HashLife::new()),
+    ]
+}
+
+fn soup(seed: u64, size: i64, density: u64) -> Vec<(i64, i64)> {
+    let mut rng = Rng(seed);
+    let mut cells = Vec::new();
+    for y in 0..size {
+        for x in 0..size {
+            if rng.next() % 100 < density {
+                cells.push((x - size / 2, y - size / 2));
+            }
+        }
+    }
+    cells
+}
+
+fn sorted(mut cells: Vec<(i64, i64, u8)>) -> Vec<(i64, i64, u8)> {
+    cells.sort();
+    cells
+}
+
+fn run_differential(rule: &str, seeds: std::ops::Range<u64>, generations: usize) {
+    let rule = Rule::parse(rule).unwrap();
+    for seed in seeds {
+        let mut engines = engines();
+        for engine in engines.iter_mut() {
+            engine.set_rule(rule);
+            for (x, y) in soup(seed, 16, 50) {
+                engine.set_cell(x, y, true);
+            }
+        }
+        for generation in 0..generations {
+            let reference = sorted(engines[0].live_cells());
+            for engine in engines.iter().skip(1) {
+                assert_eq!(
+                    sorted(engine.live_cells()),
+                    reference,
+                    "{} differs for rule {} seed {} at generation {}",
+                    engine.name(),
+                    rule,
+                    seed,
+                    generation
+                );
+                assert_eq!(engine.population(), reference.len() as u64);
+                assert_eq!(engine.bounding_box(), engines[0].bounding_box());
+            }
+            for engine in engines.iter_mut() {
+                engine.life_step();
+            }
+        }
+    }
+}
+
+#[test]
+fn life_soups() {
+    run_differential("B3/S23", 1..6, 60);
+}
+
+#[test]
+fn highlife_soups() {
+    run_differential("B36/S23", 10..13, 40);
+}
+
+#[test]
+fn hensel_soups() {
+    run_differential("B2-a/S12", 20..23, 30);
+    run_differential("B3/S2-i34q", 23..26, 30);
+}
+
+#[test]
+fn generations_soups() {
+    run_differential("B2/S/C3", 30..33, 30);
+    run_differential("B2/S345/C4", 33..36, 30);
+}
+
+#[test]
+fn get_world_matches() {
+    let mut engines = engines();
+    for engine in engines.iter_mut() {
+        for (x, y) in soup(99, 16, 40) {
+            engine.set_cell(x, y, true);
+        }
+        for _ in 0..20 {
+            engine.life_step();
+        }
+    }
+    let win = engines[0].bounding_box().unwrap();
+    let (reference, _) = engines[0].get_world(win);
+    for engine in engines.iter().skip(1) {
+        let (data, _) = engine.get_world(win);
+        assert_eq!(data, reference, "{}", engine.name());
+        assert_eq!(engine.get_cell(win.x1, win.y1), engines[0].get_cell(win.x1, win.y1));
+    }
+}
+
+#[test]
+fn worlds_move_between_threads() {
+    let mut world = World::new();
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        world.set_cell(x, y, true);
+    }
+    let world = std::thread::spawn(move || {
+        for _ in 0..4 {
+            world.life_step();
+        }
+        world
+    })
+    .join()
+    .unwrap();
+    assert_eq!(world.get_cell(1, 0), 0);
+    assert_eq!(world.get_cell(3, 3), 1);
+
+    let engine: Box<dyn LifeEngine> = Box::new(HashLife::new());
+    let engine = std::thread::spawn(move || engine).join().unwrap();
+    assert_eq!(engine.population(), 0);
+}
+
+#[test]
+fn distant_cells() {
+    // Chunks far away from each other don't need anything in between
+    let mut world = World::new();
+    let far = 1 << 40;
+    for (x, y) in [(-far, -far), (far, far), (far, -far)] {
+        for i in 0..3 {
+            world.set_cell(x + i, y, true);
+        }
+    }
+    world.life_step();
+    assert_eq!(world.population(), 9);
+    assert_eq!(world.get_cell(far + 1, far - 1), 1);
+    assert_eq!(world.get_cell(-far + 1, -far + 1), 1);
+}
+
+#[test]
+fn threaded_stepping_is_deterministic() {
+    for rule in ["B3/S23", "B3/S2-i34q", "B2/S345/C4"] {
+        let rule = Rule::parse(rule).unwrap();
+        let mut worlds: Vec<World> = [1, 2, 4, 7]
+            .iter()
+            .map(|threads| {
+                let mut world = World::new();
+                world.set_threads(*threads);
+                world.set_rule(rule);
+                for (x, y) in soup(7, 160, 35) {
+                    world.set_cell(x, y, true);
+                }
+                world
+            })
+            .collect();
+        for _ in 0..20 {
+            for world in worlds.iter_mut() {
+                world.life_step();
+            }
+        }
+        let win = worlds[0].size();
+        let (reference, _) = worlds[0].get_world(win);
+        for world in worlds.iter().skip(1) {
+            assert_eq!(world.size(), win, "{} threads, rule {}", world.threads(), rule);
+            assert_eq!(world.get_world(win).0, reference, "{} threads, rule {}", world.threads(), rule);
+        }
+    }
+}
+
+#[test]
+fn generation_counter() {
+    for mut engine in engines() {
+        for (x, y) in soup(3, 8, 50) {
+            engine.set_cell(x, y, true);
+        }
+        engine.life_step();
+        engine.step_pow2(3);
+        assert_eq!(engine.generation(), 9, "{}", engine.name());
+        let mut next = next_engine(engine.as_ref());
+        transfer(engine.as_ref(), next.as_mut());
+        assert_eq!(next.generation(), 9, "{}", next.name());
+    }
+}
+
+#[test]
+fn oversized_pow2_steps_are_rejected() {
+    let mut engines: Vec<Box<dyn LifeEngine>> = vec![Box::new(DenseWorld::new()), Box::new(World::new())];
+    for engine in engines.iter_mut() {
+        engine.set_cell(0, 0, true);
+        assert!(!engine.step_pow2(64), "{}", engine.name());
+        assert!(!engine.step_pow2(u8::MAX), "{}", engine.name());
+        assert_eq!(engine.generation(), 0);
+        assert!(engine.step_pow2(1));
+        assert_eq!(engine.generation(), 2);
+    }
+}
+
+#[test]
+fn leaving_generations_rules_drops_decay_states() {
+    for mut engine in engines() {
+        engine.set_rule(Rule::parse("B2/S345/C4").unwrap());
+        engine.set_state(0, 0, 1);
+        engine.set_state(5, 0, 2);
+        engine.set_state(9, 9, 3);
+        engine.set_rule(Rule::parse("B2/S345/C3").unwrap());
+        assert_eq!(sorted(engine.live_cells()), [(0, 0, 1), (5, 0, 2)], "{}", engine.name());
+        engine.set_rule(Rule::parse("B3/S23").unwrap());
+        assert_eq!(engine.live_cells(), [(0, 0, 1)], "{}", engine.name());
+    }
+    let mut world = World::new();
+    world.set_history(true);
+    world.set_rule(Rule::parse("B2/S345/C4").unwrap());
+    world.set_state(5, 0, 3);
+    world.set_rule(Rule::life());
+    assert_eq!(world.get_cell(5, 0), 0);
+    assert!(world.undo());
+    assert!(world.undo());
+    assert_eq!(world.get_cell(5, 0), 3);
+}
@@ -0,0 +1,95 @@
+use gol::{read_cells, read_life105, read_life106, read_pattern, write_cells, write_life106, Format, Rule};
+
+const GLIDER_CELLS: &str = "!Name: Glider
+!The smallest spaceship
+.O
+..O
+OOO
+";
+
+const GLIDER_LIFE106: &str = "#Life 1.06
+1 0
+2 1
+0 2
+1 2
+2 2
+";
+
+const GLIDER_LIFE105: &str = "#Life 1.05
+#D Glider
+#R 23/36
+#P -1 -1
+.*
+..*
+***
+";
+
+fn sorted(mut cells: Vec<(i64, i64, u8)>) -> Vec<(i64, i64, u8)> {
+    cells.sort();
+    cells
+}
+
+fn glider() -> Vec<(i64, i64, u8)> {
+    vec![(0, 2, 1), (1, 0, 1), (1, 2, 1), (2, 1, 1), (2, 2, 1)]
+}
+
+#[test]
+fn plaintext_round_trip() {
+    let pattern = read_cells(GLIDER_CELLS).unwrap();
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(pattern.comments, vec!["The smallest spaceship".to_string()]);
+    assert_eq!((pattern.width, pattern.height), (3, 3));
+    assert_eq!(sorted(pattern.cells.clone()), glider());
+    assert_eq!(write_cells(&pattern), GLIDER_CELLS);
+}
+
+#[test]
+fn plaintext_blank_rows() {
+    let text = "O\n\n.O\n";
+    let pattern = read_cells(text).unwrap();
+    assert_eq!(pattern.height, 3);
+    assert_eq!(write_cells(&pattern), text);
+}
+
+#[test]
+fn life106_round_trip() {
+    let pattern = read_life106(GLIDER_LIFE106).unwrap();
+    assert_eq!(sorted(pattern.cells.clone()), glider());
+    assert_eq!(write_life106(&pattern), GLIDER_LIFE106);
+    // Negative coordinates are moved to the top left corner
+    let shifted = read_life106("#Life 1.06\n-5 -5\n-4 -5\n").unwrap();
+    assert_eq!(shifted.cells, vec![(0, 0, 1), (1, 0, 1)]);
+    assert_eq!((shifted.width, shifted.height), (2, 1));
+}
+
+#[test]
+fn life105_blocks() {
+    let pattern = read_life105(GLIDER_LIFE105).unwrap();
+    assert_eq!(pattern.comments, vec!["Glider".to_string()]);
+    assert_eq!(pattern.rule, Some(Rule::parse("B36/S23").unwrap()));
+    assert_eq!(sorted(pattern.cells.clone()), glider());
+    let blocks = read_life105("#Life 1.05\n#N\n#P 0 0\n*\n#P 10 -3\n**\n").unwrap();
+    assert_eq!(blocks.rule, Some(Rule::life()));
+    assert_eq!(sorted(blocks.cells), vec![(0, 3, 1), (10, 0, 1), (11, 0, 1)]);
+}
+
+#[test]
+fn detect_format() {
+    assert_eq!(Format::detect(GLIDER_CELLS), Format::Plaintext);
+    assert_eq!(Format::detect(".O\n..O\nOOO\n"), Format::Plaintext);
+    assert_eq!(Format::detect(GLIDER_LIFE106), Format::Life106);
+    assert_eq!(Format::detect("0 0\n1 0\n"), Format::Life106);
+    assert_eq!(Format::detect(GLIDER_LIFE105), Format::Life105);
+    assert_eq!(Format::detect("#N Glider\nx = 3, y = 3\nbo$2bo$3o!\n"), Format::Rle);
+    assert_eq!(Format::detect("bo$2bo$3o!"), Format::Rle);
+    for text in [GLIDER_CELLS, GLIDER_LIFE106, GLIDER_LIFE105, "x = 3, y = 3\nbo$2bo$3o!"] {
+        assert_eq!(sorted(read_pattern(text).unwrap().cells), glider(), "{:?}", text);
+    }
+}
+
+#[test]
+fn invalid_files() {
+    assert!(read_cells("!x\n.X.\n").is_err());
+    assert!(read_life106("#Life 1.06\n1 2 3\n").is_err());
+    assert!(read_life105("#Life 1.05\n#P a b\n*\n").is_err());
+}
@@ -0,0 +1,93 @@
+use gol::{HashLife, Rule, World};
+
+const GLIDER: [(i64, i64); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+const R_PENTOMINO: [(i64, i64); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+fn sorted(mut cells: Vec<(i64, i64, u8)>) -> Vec<(i64, i64, u8)> {
+    cells.sort();
+    cells
+}
+
+#[test]
+fn single_steps_match_chunk_world() {
+    let mut life = HashLife::new();
+    let mut world = World::new();
+    for (x, y) in R_PENTOMINO {
+        life.set_cell(x, y, true);
+        world.set_cell(x, y, true);
+    }
+    for _ in 0..200 {
+        life.life_step();
+        world.life_step();
+    }
+    assert_eq!(sorted(life.live_cells()), sorted(world.live_cells()));
+}
+
+#[test]
+fn glider_after_power_of_two_generations() {
+    let mut life = HashLife::new();
+    for (x, y) in GLIDER {
+        life.set_cell(x, y, true);
+    }
+    // 2^12 generations move the glider 1024 cells diagonally
+    life.step_pow2(12);
+    let expected: Vec<(i64, i64, u8)> = GLIDER.iter().map(|(x, y)| (x + 1024, y + 1024, 1)).collect();
+    assert_eq!(sorted(life.live_cells()), sorted(expected));
+    assert_eq!(life.get_cell(1025, 1024), 1);
+}
+
+#[test]
+fn r_pentomino_stabilises() {
+    let mut life = HashLife::new();
+    for (x, y) in R_PENTOMINO {
+        life.set_cell(x, y, true);
+    }
+    // 1103 generations stabilise at 116 cells, escaping gliders included
+    life.step_pow2(10);
+    for _ in 0..79 {
+        life.life_step();
+    }
+    assert_eq!(life.live_cells().len(), 116);
+}
+
+#[test]
+fn generations_rule_matches_chunk_world() {
+    let rule = Rule::parse("B2/S345/C4").unwrap();
+    let mut life = HashLife::new();
+    let mut world = World::new();
+    life.set_rule(rule);
+    world.set_rule(rule);
+    for (x, y) in [(0, 0), (1, 0), (2, 1), (0, 2), (5, 5), (6, 5)] {
+        life.set_cell(x, y, true);
+        world.set_cell(x, y, true);
+    }
+    life.step_pow2(4);
+    for _ in 0..16 {
+        world.life_step();
+    }
+    assert_eq!(sorted(life.live_cells()), sorted(world.live_cells()));
+}
+
+#[test]
+fn huge_steps_are_rejected() {
+    let mut life = HashLife::new();
+    for (x, y) in GLIDER {
+        life.set_cell(x, y, true);
+    }
+    for j in [60, 62, 63, 64, u8::MAX] {
+        assert!(!life.step_pow2(j), "2^{}", j);
+    }
+    assert_eq!(life.generation(), 0);
+    assert_eq!(life.population(), 5);
+    // Glider moves a quarter cell per generation, far but still in range
+    assert!(life.step_pow2(40));
+    assert_eq!(life.population(), 5);
+    assert_eq!(life.bounding_box().unwrap().x1, 1 << 38);
+    life.set_generation(u64::MAX - 1);
+    assert!(!life.step_pow2(1));
+    assert!(life.step_pow2(0));
+    assert_eq!(life.generation(), u64::MAX);
+    // Cells out of reach of the root are dropped instead of growing forever
+    life.set_cell(i64::MIN, i64::MAX, true);
+    assert_eq!(life.population(), 5);
+}
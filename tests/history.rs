@@ -0,0 +1,179 @@
+use gol::{soup, LifeEngine, Rule, World};
+
+const GLIDER: [(i64, i64); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+fn sorted(mut cells: Vec<(i64, i64, u8)>) -> Vec<(i64, i64, u8)> {
+    cells.sort();
+    cells
+}
+
+fn glider_world() -> World {
+    let mut world = World::new();
+    world.set_history(true);
+    for (x, y) in GLIDER {
+        world.set_cell(x, y, true);
+    }
+    world
+}
+
+#[test]
+fn undo_and_redo_edits() {
+    let mut world = glider_world();
+    world.commit_edit();
+    world.set_cell(10, 10, true);
+    world.set_cell(11, 10, true);
+    world.set_cell(1, 0, false);
+    assert!(world.undo());
+    assert_eq!(sorted(world.live_cells()), sorted(glider_world().live_cells()));
+    assert!(world.redo());
+    assert_eq!(world.get_cell(10, 10), 1);
+    assert_eq!(world.get_cell(1, 0), 0);
+    assert!(world.undo());
+    assert!(world.undo());
+    assert_eq!(world.population(), 0);
+    assert!(!world.undo());
+    assert!(!world.can_undo());
+}
+
+#[test]
+fn undo_steps() {
+    let mut world = glider_world();
+    let mut generations = vec![sorted(world.live_cells())];
+    // Long enough to need several snapshots
+    for _ in 0..150 {
+        world.life_step();
+        generations.push(sorted(world.live_cells()));
+    }
+    for generation in (0..150).rev() {
+        assert!(world.undo());
+        assert_eq!(world.generation(), generation as u64);
+        assert_eq!(sorted(world.live_cells()), generations[generation]);
+    }
+    // Last undo removes the glider itself
+    assert!(world.undo());
+    assert_eq!(world.population(), 0);
+    assert!(world.redo());
+    for cells in generations.iter().skip(1) {
+        assert!(world.redo());
+        assert_eq!(&sorted(world.live_cells()), cells);
+    }
+    assert!(!world.redo());
+}
+
+#[test]
+fn edits_between_steps() {
+    let mut world = glider_world();
+    world.step_pow2(3);
+    world.set_cell(-20, -20, true);
+    world.set_cell(-19, -20, true);
+    world.set_cell(-18, -20, true);
+    let after_edit = sorted(world.live_cells());
+    world.life_step();
+    world.life_step();
+    assert!(world.undo());
+    assert!(world.undo());
+    assert_eq!(sorted(world.live_cells()), after_edit);
+    assert!(world.undo());
+    assert_eq!(world.get_cell(-19, -20), 0);
+    assert_eq!(world.generation(), 8);
+    // New edit drops what could have been redone
+    world.set_cell(0, 0, true);
+    assert!(!world.redo());
+    assert!(!world.can_redo());
+}
+
+#[test]
+fn generations_states_are_restored() {
+    let mut world = World::new();
+    world.set_history(true);
+    world.set_rule(Rule::parse("B2/S345/C4").unwrap());
+    world.set_cell(0, 0, true);
+    world.set_cell(1, 0, true);
+    world.set_cell(0, 1, true);
+    let mut generations = vec![sorted(world.live_cells())];
+    for _ in 0..70 {
+        world.life_step();
+        generations.push(sorted(world.live_cells()));
+    }
+    for generation in (60..70).rev() {
+        world.undo();
+        assert_eq!(sorted(world.live_cells()), generations[generation]);
+    }
+}
+
+#[test]
+fn history_can_be_disabled() {
+    // Off unless asked for
+    let mut world = World::new();
+    world.set_cell(0, 0, true);
+    world.life_step();
+    assert!(!world.can_undo());
+    assert!(!world.undo());
+
+    let mut world = glider_world();
+    world.set_history(false);
+    world.set_cell(0, 0, true);
+    world.life_step();
+    assert!(!world.can_undo());
+    assert!(!world.undo());
+}
+
+#[test]
+fn rule_changes_are_undone() {
+    let life = Rule::parse("B3/S23").unwrap();
+    let highlife = Rule::parse("B36/S23").unwrap();
+    let mut world = World::new();
+    world.set_history(true);
+    soup(24, 24, 0.45, 11).place(&mut world, 0, 0);
+    let mut generations = vec![sorted(world.live_cells())];
+    for i in 0..20 {
+        if i == 10 {
+            world.set_rule(highlife);
+        }
+        world.life_step();
+        generations.push(sorted(world.live_cells()));
+    }
+    for generation in (0..20).rev() {
+        if generation == 9 {
+            assert_eq!(world.rule(), highlife);
+            assert!(world.undo());
+            assert_eq!(world.rule(), life);
+        }
+        assert!(world.undo());
+        assert_eq!(sorted(world.live_cells()), generations[generation], "generation {}", generation);
+    }
+    for _ in 0..11 {
+        assert!(world.redo());
+    }
+    assert_eq!(world.rule(), highlife);
+    assert!(world.redo());
+    assert_eq!(sorted(world.live_cells()), generations[11]);
+}
+
+#[test]
+fn replaced_worlds_are_undone() {
+    let mut world = glider_world();
+    for _ in 0..3 {
+        world.life_step();
+    }
+    let before = sorted(world.live_cells());
+    let mut other = World::new();
+    other.set_rule(Rule::parse("B2/S/C3").unwrap());
+    other.set_cell(40, 40, true);
+    other.set_generation(100);
+    assert!(LifeEngine::replace(&mut world, &other));
+    assert_eq!(world.generation(), 100);
+    assert_eq!(world.rule(), other.rule());
+    assert_eq!(sorted(world.live_cells()), [(40, 40, 1)]);
+
+    assert!(world.undo());
+    assert_eq!(world.generation(), 3);
+    assert_eq!(world.rule(), Rule::life());
+    assert_eq!(sorted(world.live_cells()), before);
+    assert!(world.undo());
+    assert_eq!(world.generation(), 2);
+    assert!(world.redo());
+    assert!(world.redo());
+    assert_eq!(world.generation(), 100);
+    assert_eq!(sorted(world.live_cells()), [(40, 40, 1)]);
+}
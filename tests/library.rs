@@ -0,0 +1,87 @@
+use gol::{search_library, LifeEngine, Pattern, World, LIBRARY};
+
+fn world(name: &str) -> World {
+    let entry = LIBRARY.iter().find(|entry| entry.name == name).unwrap();
+    let mut world = World::new();
+    entry.pattern().place(&mut world, 0, 0);
+    world
+}
+
+fn run(world: &mut World, generations: u32) {
+    for _ in 0..generations {
+        world.life_step();
+    }
+}
+
+fn cells(world: &World) -> Pattern {
+    let mut pattern = Pattern::from_engine(world);
+    pattern.cells.sort_by_key(|cell| (cell.1, cell.0));
+    pattern.rule = None;
+    pattern
+}
+
+#[test]
+fn bundled_patterns_parse() {
+    for entry in LIBRARY {
+        let pattern = entry.pattern();
+        assert_eq!(pattern.name.as_deref(), Some(entry.name), "{}", entry.name);
+        assert!(pattern.population() > 0, "{}", entry.name);
+        assert!(pattern.cells.iter().all(|c| c.0 < pattern.width && c.1 < pattern.height), "{}", entry.name);
+    }
+}
+
+#[test]
+fn still_lifes_and_oscillators() {
+    for entry in LIBRARY.iter() {
+        let period = match entry.name {
+            "Blinker" | "Toad" | "Beacon" => 2,
+            "Pulsar" => 3,
+            "Pentadecathlon" => 15,
+            _ if entry.category == "still life" => 1,
+            _ => continue,
+        };
+        let mut world = world(entry.name);
+        let start = cells(&world);
+        run(&mut world, 1);
+        assert_eq!(cells(&world) == start, period == 1, "{}", entry.name);
+        run(&mut world, period - 1);
+        assert_eq!(cells(&world), start, "{}", entry.name);
+    }
+}
+
+#[test]
+fn spaceships_move() {
+    for entry in LIBRARY.iter().filter(|entry| entry.category == "spaceship") {
+        let mut world = world(entry.name);
+        let start = cells(&world);
+        let rect = world.bounding_box().unwrap();
+        run(&mut world, 4);
+        assert_eq!(cells(&world), start, "{}", entry.name);
+        assert_ne!(world.bounding_box().unwrap(), rect, "{}", entry.name);
+    }
+}
+
+#[test]
+fn gun_and_methuselahs() {
+    let mut gun = world("Gosper glider gun");
+    run(&mut gun, 120);
+    // One new glider every 30 generations
+    assert_eq!(gun.population(), 36 + 4 * 5);
+
+    let mut diehard = world("Diehard");
+    run(&mut diehard, 129);
+    assert!(diehard.population() > 0);
+    run(&mut diehard, 1);
+    assert_eq!(diehard.population(), 0);
+}
+
+#[test]
+fn search() {
+    assert_eq!(search_library("").len(), LIBRARY.len());
+    let names: Vec<_> = search_library("GLIDER").iter().map(|entry| entry.name).collect();
+    assert_eq!(names, ["Glider", "Gosper glider gun"]);
+    let names: Vec<_> = search_library("weight ship heavy").iter().map(|entry| entry.name).collect();
+    assert_eq!(names, ["Heavyweight spaceship"]);
+    assert_eq!(search_library("methuselah").len(), 3);
+    assert!(search_library("nothing like this").is_empty());
+}
@@ -0,0 +1,227 @@
+use gol::{LifeEngine, Vec4, World};
+
+fn world_from(cells: &[(i64, i64)]) -> World {
+    let mut world = World::new();
+    for (x, y) in cells {
+        world.set_cell(*x, *y, true);
+    }
+    world
+}
+
+fn live_cells(world: &World, win: Vec4<i64>) -> Vec<(i64, i64)> {
+    let (data, _) = world.get_world(win);
+    let width = win.size().x;
+    let mut cells = Vec::new();
+    for (i, alive) in data.iter().enumerate() {
+        if *alive == 1 {
+            cells.push((win.x1 + i as i64 % width, win.y1 + i as i64 / width));
+        }
+    }
+    cells
+}
+
+fn shifted(cells: &[(i64, i64)], dx: i64, dy: i64) -> Vec<(i64, i64)> {
+    let mut cells: Vec<(i64, i64)> = cells.iter().map(|(x, y)| (x + dx, y + dy)).collect();
+    cells.sort_by_key(|(x, y)| (*y, *x));
+    cells
+}
+
+fn steps(world: &mut World, n: usize) {
+    for _ in 0..n {
+        world.life_step();
+    }
+}
+
+const WIN: Vec4<i64> = Vec4 { x1: -100, y1: -100, x2: 100, y2: 100 };
+
+const BLINKER: [(i64, i64); 3] = [(1, 0), (1, 1), (1, 2)];
+const GLIDER: [(i64, i64); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+const LWSS: [(i64, i64); 9] = [
+    (1, 0), (4, 0),
+    (0, 1),
+    (0, 2), (4, 2),
+    (0, 3), (1, 3), (2, 3), (3, 3),
+];
+
+#[test]
+fn empty_world_stays_empty() {
+    let mut world = World::new();
+    steps(&mut world, 3);
+    assert!(live_cells(&world, WIN).is_empty());
+}
+
+#[test]
+fn block_is_still_life() {
+    let block = [(0, 0), (1, 0), (0, 1), (1, 1)];
+    let mut world = world_from(&block);
+    steps(&mut world, 5);
+    assert_eq!(live_cells(&world, WIN), shifted(&block, 0, 0));
+}
+
+#[test]
+fn beehive_is_still_life() {
+    let beehive = [(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (2, 2)];
+    let mut world = world_from(&beehive);
+    steps(&mut world, 4);
+    assert_eq!(live_cells(&world, WIN), shifted(&beehive, 0, 0));
+}
+
+#[test]
+fn lone_cells_die() {
+    let mut world = world_from(&[(0, 0), (10, 10)]);
+    world.life_step();
+    assert!(live_cells(&world, WIN).is_empty());
+}
+
+#[test]
+fn blinker_oscillates() {
+    let mut world = world_from(&BLINKER);
+    world.life_step();
+    assert_eq!(live_cells(&world, WIN), vec![(0, 1), (1, 1), (2, 1)]);
+    world.life_step();
+    assert_eq!(live_cells(&world, WIN), shifted(&BLINKER, 0, 0));
+}
+
+#[test]
+fn blinker_across_chunk_corner() {
+    // Every cell of the blinker sits in a different chunk
+    let blinker = shifted(&BLINKER, -1, -1);
+    let mut world = world_from(&blinker);
+    world.life_step();
+    assert_eq!(live_cells(&world, WIN), vec![(-1, 0), (0, 0), (1, 0)]);
+    world.life_step();
+    assert_eq!(live_cells(&world, WIN), blinker);
+}
+
+#[test]
+fn toad_has_period_two() {
+    let toad = [(1, 0), (2, 0), (3, 0), (0, 1), (1, 1), (2, 1)];
+    let mut world = world_from(&toad);
+    world.life_step();
+    assert_ne!(live_cells(&world, WIN), shifted(&toad, 0, 0));
+    world.life_step();
+    assert_eq!(live_cells(&world, WIN), shifted(&toad, 0, 0));
+}
+
+#[test]
+fn beacon_has_period_two() {
+    let beacon = [(0, 0), (1, 0), (0, 1), (3, 2), (2, 3), (3, 3)];
+    let mut world = world_from(&beacon);
+    world.life_step();
+    assert_eq!(live_cells(&world, WIN).len(), 8);
+    world.life_step();
+    assert_eq!(live_cells(&world, WIN), shifted(&beacon, 0, 0));
+}
+
+#[test]
+fn pulsar_has_period_three() {
+    let mut pulsar = Vec::new();
+    for a in [2, 3, 4, 8, 9, 10] {
+        for b in [0, 5, 7, 12] {
+            pulsar.push((a, b));
+            pulsar.push((b, a));
+        }
+    }
+    let pulsar = shifted(&pulsar, -6, -6);
+    let mut world = world_from(&pulsar);
+    for _ in 0..2 {
+        world.life_step();
+        assert_ne!(live_cells(&world, WIN), pulsar);
+    }
+    world.life_step();
+    assert_eq!(live_cells(&world, WIN), pulsar);
+}
+
+#[test]
+fn glider_moves_diagonally() {
+    let mut world = world_from(&GLIDER);
+    steps(&mut world, 4);
+    assert_eq!(live_cells(&world, WIN), shifted(&GLIDER, 1, 1));
+    // Travel over several chunk borders
+    steps(&mut world, 36);
+    assert_eq!(live_cells(&world, WIN), shifted(&GLIDER, 10, 10));
+}
+
+#[test]
+fn glider_moves_into_negative_coordinates() {
+    // Glider flying up and to the left
+    let glider: Vec<(i64, i64)> = GLIDER.iter().map(|(x, y)| (-x, -y)).collect();
+    let mut world = world_from(&glider);
+    steps(&mut world, 40);
+    assert_eq!(live_cells(&world, WIN), shifted(&glider, -10, -10));
+    let size = world.size();
+    assert!(size.x1 < -12 && size.y1 < -12);
+}
+
+#[test]
+fn lwss_moves_orthogonally() {
+    let mut world = world_from(&LWSS);
+    steps(&mut world, 4);
+    assert_eq!(live_cells(&world, WIN), shifted(&LWSS, -2, 0));
+    steps(&mut world, 36);
+    assert_eq!(live_cells(&world, WIN), shifted(&LWSS, -20, 0));
+}
+
+#[test]
+fn r_pentomino_population() {
+    // Well known population after 100 generations
+    let mut world = world_from(&[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)]);
+    steps(&mut world, 100);
+    let win = Vec4 { x1: -200, y1: -200, x2: 200, y2: 200 };
+    assert_eq!(live_cells(&world, win).len(), 121);
+}
+
+#[test]
+fn empty_chunks_are_collected() {
+    let mut world = world_from(&GLIDER);
+    world.set_gc_idle(8);
+    // Glider crosses several chunks, only those around it are kept
+    steps(&mut world, 4 * 256);
+    assert_eq!(live_cells(&world, Vec4 { x1: 250, y1: 250, x2: 270, y2: 270 }), shifted(&GLIDER, 256, 256));
+    assert!(world.chunk_count() <= 9, "{} chunks", world.chunk_count());
+    let size = world.size();
+    assert!(size.x1 > 0 && size.y1 > 0 && size.x2 > 258 && size.y2 > 258);
+    assert!(size.x2 - size.x1 <= 128 && size.y2 - size.y1 <= 128);
+}
+
+#[test]
+fn dead_world_shrinks_back() {
+    let mut world = world_from(&[(1000, 1000), (-1000, -1000)]);
+    world.set_gc_idle(0);
+    world.life_step();
+    assert_eq!(world.alive_chunk_count(), 0);
+    assert_eq!(world.chunk_count(), 0);
+    assert_eq!(world.size(), World::new().size());
+}
+
+#[test]
+fn erasing_cells() {
+    let mut world = world_from(&BLINKER);
+    world.set_cell(1, 1, false);
+    assert_eq!(world.get_cell(1, 1), 0);
+    assert_eq!(world.alive_chunk_count(), 1);
+    world.set_cell(1, 0, false);
+    world.set_cell(1, 2, false);
+    // Emptied chunk is not stepped anymore
+    assert_eq!(world.alive_chunk_count(), 0);
+    // Erasing where nothing lives creates nothing
+    let size = world.size();
+    world.set_cell(5000, -5000, false);
+    assert_eq!(world.size(), size);
+    assert_eq!(world.chunk_count(), 1);
+    steps(&mut world, 1);
+    assert_eq!(live_cells(&world, WIN), vec![]);
+}
+
+#[test]
+fn toggling_cells() {
+    let mut world = world_from(&BLINKER);
+    assert_eq!(world.toggle_cell(1, 1), 0);
+    assert_eq!(world.toggle_cell(1, 1), 1);
+    assert_eq!(world.toggle_cell(70, 70), 1);
+    assert_eq!(world.alive_chunk_count(), 2);
+    assert_eq!(world.toggle_cell(70, 70), 0);
+    assert_eq!(world.alive_chunk_count(), 1);
+    steps(&mut world, 1);
+    assert_eq!(live_cells(&world, WIN), vec![(0, 1), (1, 1), (2, 1)]);
+}
@@ -0,0 +1,98 @@
+use gol::{read_macrocell, read_pattern, write_macrocell, HashLife, Rule, World};
+
+const GLIDER: &str = "[M2] (golly 2.0)
+#R B3/S23
+.*$..*$***$
+";
+
+fn sorted(mut cells: Vec<(i64, i64, u8)>) -> Vec<(i64, i64, u8)> {
+    cells.sort();
+    cells
+}
+
+#[test]
+fn read_glider() {
+    let life = read_macrocell(GLIDER).unwrap();
+    assert_eq!(life.rule(), Rule::life());
+    // Root is centred on 0, 0
+    assert_eq!(
+        sorted(life.live_cells()),
+        vec![(-4, -2, 1), (-3, -4, 1), (-3, -2, 1), (-2, -3, 1), (-2, -2, 1)]
+    );
+    assert_eq!(read_pattern(GLIDER).unwrap().population(), 5);
+}
+
+#[test]
+fn round_trip_stepped_pattern() {
+    let mut life = HashLife::new();
+    for (x, y) in [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)] {
+        life.set_cell(x, y, true);
+    }
+    life.step_pow2(10);
+    let text = write_macrocell(&life);
+    assert!(text.contains("#G 1024\n"));
+    let read = read_macrocell(&text).unwrap();
+    assert_eq!(read.generation(), 1024);
+    assert_eq!(sorted(read.live_cells()), sorted(life.live_cells()));
+    assert_eq!(write_macrocell(&read), text);
+}
+
+#[test]
+fn generations_states() {
+    let rule = Rule::parse("B2/S345/C4").unwrap();
+    let mut life = HashLife::new();
+    life.set_rule(rule);
+    for (x, y, state) in [(0, 0, 1), (1, 0, 2), (5, 3, 3), (-9, 20, 1)] {
+        life.set_state(x, y, state);
+    }
+    let text = write_macrocell(&life);
+    assert!(text.lines().any(|line| line.starts_with("1 ")));
+    let read = read_macrocell(&text).unwrap();
+    assert_eq!(read.rule(), rule);
+    assert_eq!(sorted(read.live_cells()), sorted(life.live_cells()));
+}
+
+#[test]
+fn any_engine_is_written() {
+    let mut world = World::new();
+    let mut life = HashLife::new();
+    for (x, y) in [(3, 3), (4, 3), (5, 3), (-70, 100)] {
+        world.set_cell(x, y, true);
+        life.set_cell(x, y, true);
+    }
+    let read = read_macrocell(&write_macrocell(&world)).unwrap();
+    assert_eq!(sorted(read.live_cells()), sorted(world.live_cells()));
+    assert_eq!(write_macrocell(&world), write_macrocell(&life));
+    assert_eq!(read_macrocell(&write_macrocell(&HashLife::new())).unwrap().population(), 0);
+}
+
+#[test]
+fn huge_pattern() {
+    // Block repeated 4^27 times, every level refers to the one below
+    let mut text = String::from("[M2]\n**$**$\n");
+    for level in 4..=30 {
+        let id = level - 3;
+        text += &format!("{} {} {} {} {}\n", level, id, id, id, id);
+    }
+    let life = read_macrocell(&text).unwrap();
+    assert_eq!(life.population(), 4 << 54);
+    let half = 1i64 << 29;
+    assert_eq!(life.get_cell(-half, -half), 1);
+    assert_eq!(life.get_cell(-half + 2, -half), 0);
+    assert_eq!(life.get_cell(half - 7, half - 7), 1);
+}
+
+#[test]
+fn invalid_files() {
+    for text in [
+        ".*$\n",
+        "[M2]\n",
+        "[M2]\n4 1 0 0 0\n",
+        "[M2]\n.*$\n5 1 0 0 0\n",
+        "[M2]\n.*x$\n",
+        "[M2]\n#R B9\n.*$\n",
+        "[M2]\n1 0 0 0\n",
+    ] {
+        assert!(read_macrocell(text).is_err(), "{:?}", text);
+    }
+}
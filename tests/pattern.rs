@@ -0,0 +1,67 @@
+use gol::{read_rle, LifeEngine, Pattern, World};
+
+// L tromino with a tail, no symmetry so every transform is distinct
+fn shape() -> Pattern {
+    read_rle("x = 3, y = 2\n3o$o!").unwrap()
+}
+
+fn rows(pattern: &Pattern) -> Vec<String> {
+    (0..pattern.height)
+        .map(|y| {
+            (0..pattern.width)
+                .map(|x| if pattern.get(x, y) == 0 { '.' } else { 'o' })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn rotations() {
+    let mut pattern = shape();
+    pattern.rotate_cw();
+    assert_eq!(rows(&pattern), ["oo", ".o", ".o"]);
+    pattern.rotate_cw();
+    let mut half = shape();
+    half.rotate_180();
+    assert_eq!(pattern, half);
+    assert_eq!(rows(&half), ["..o", "ooo"]);
+    pattern.rotate_cw();
+    let mut ccw = shape();
+    ccw.rotate_ccw();
+    assert_eq!(pattern, ccw);
+    pattern.rotate_cw();
+    assert_eq!(pattern, shape());
+}
+
+#[test]
+fn mirrors() {
+    let mut pattern = shape();
+    pattern.flip_horizontal();
+    assert_eq!(rows(&pattern), ["ooo", "..o"]);
+    pattern.flip_vertical();
+    let mut half = shape();
+    half.rotate_180();
+    assert_eq!(pattern, half);
+
+    let mut pattern = shape();
+    pattern.transpose();
+    assert_eq!(rows(&pattern), ["oo", "o.", "o."]);
+    pattern.transpose();
+    assert_eq!(pattern, shape());
+}
+
+#[test]
+fn transformed_glider_still_flies() {
+    let mut glider = read_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+    glider.rotate_cw();
+    let mut world = World::new();
+    glider.place(&mut world, 0, 0);
+    for _ in 0..4 {
+        world.life_step();
+    }
+    // Clockwise turns the south east glider into a south west one
+    let moved = Pattern::from_engine(&world);
+    assert_eq!(rows(&moved), rows(&glider));
+    assert_eq!(world.bounding_box().unwrap().x1, -1);
+    assert_eq!(world.bounding_box().unwrap().y1, 1);
+}
@@ -0,0 +1,75 @@
+use gol::{read_rle, write_rle, LifeEngine, Pattern, Rule, Vec4, World};
+
+const GLIDER: &str = "#N Glider
+#C The smallest spaceship
+x = 3, y = 3, rule = B3/S23
+bo$2bo$3o!
+";
+
+const GOSPER_GUN: &str = "#N Gosper glider gun
+x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!
+";
+
+#[test]
+fn read_glider() {
+    let pattern = read_rle(GLIDER).unwrap();
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(pattern.comments, vec!["The smallest spaceship".to_string()]);
+    assert_eq!(pattern.rule, Some(Rule::life()));
+    assert_eq!((pattern.width, pattern.height), (3, 3));
+    let mut cells = pattern.cells.clone();
+    cells.sort();
+    assert_eq!(cells, vec![(0, 2, 1), (1, 0, 1), (1, 2, 1), (2, 1, 1), (2, 2, 1)]);
+}
+
+#[test]
+fn round_trips() {
+    for text in [GLIDER, GOSPER_GUN] {
+        assert_eq!(write_rle(&read_rle(text).unwrap()), text);
+    }
+    assert_eq!(read_rle(GOSPER_GUN).unwrap().population(), 36);
+}
+
+#[test]
+fn generations_states() {
+    let text = "x = 5, y = 2, rule = B2/S345/C4\n.A2B$C.pA!\n";
+    let pattern = read_rle(text).unwrap();
+    assert_eq!(pattern.get(1, 0), 1);
+    assert_eq!(pattern.get(2, 0), 2);
+    assert_eq!(pattern.get(3, 0), 2);
+    assert_eq!(pattern.get(0, 1), 3);
+    assert_eq!(pattern.get(2, 1), 25);
+    assert_eq!(write_rle(&pattern), text);
+}
+
+#[test]
+fn blank_rows_and_loose_formatting() {
+    let pattern = read_rle("x=2,y=4\n  o\n\n3$ 2o\n!").unwrap();
+    assert_eq!(pattern.rule, None);
+    assert_eq!(pattern.population(), 3);
+    assert_eq!(pattern.get(0, 3), 1);
+    assert_eq!(pattern.get(1, 3), 1);
+    assert_eq!(write_rle(&pattern), "x = 2, y = 4\no3$2o!\n");
+}
+
+#[test]
+fn invalid_patterns() {
+    for text in ["x = 3, y = 3\nbo?o!", "x = a, y = 3\no!", "x = 3, y = 3, rule = B9\no!", "pq!"] {
+        assert!(read_rle(text).is_err(), "{:?}", text);
+    }
+}
+
+#[test]
+fn place_and_export() {
+    let mut world = World::new();
+    let gun = read_rle(GOSPER_GUN).unwrap();
+    gun.place(&mut world, -10, 60);
+    assert_eq!(world.get_cell(-10 + 24, 60), 1);
+    assert_eq!(world.population(), 36);
+    let rect = Vec4 { x1: -10, y1: 60, x2: 26, y2: 69 };
+    let mut exported = Pattern::from_world(&world, rect);
+    exported.name = gun.name.clone();
+    assert_eq!(write_rle(&exported), GOSPER_GUN);
+}
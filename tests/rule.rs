@@ -0,0 +1,117 @@
+use gol::{Rule, Vec4, World};
+
+#[test]
+fn parse_notations() {
+    let life = Rule::life();
+    assert_eq!(Rule::parse("B3/S23"), Ok(life));
+    assert_eq!(Rule::parse("b3/s23"), Ok(life));
+    assert_eq!(Rule::parse("S23/B3"), Ok(life));
+    assert_eq!(Rule::parse("23/3"), Ok(life));
+    assert_eq!(Rule::parse("B36/S23").unwrap().to_string(), "B36/S23");
+    assert_eq!(Rule::parse("/2").unwrap().to_string(), "B2/S");
+    assert_eq!(Rule::parse("B2/S").unwrap().name(), Some("Seeds"));
+}
+
+#[test]
+fn reject_invalid_rules() {
+    assert!(Rule::parse("B3S23").is_err());
+    assert!(Rule::parse("B39/S23").is_err());
+    assert!(Rule::parse("Bx/S23").is_err());
+    assert!(Rule::parse("B03/S23").is_err());
+}
+
+#[test]
+fn seeds_domino() {
+    // Two cells next to each other give birth on both sides and die
+    let mut world = World::new();
+    world.set_rule(Rule::parse("B2/S").unwrap());
+    world.set_cell(0, 0, true);
+    world.set_cell(1, 0, true);
+    world.life_step();
+    let win = Vec4 { x1: -3, y1: -3, x2: 4, y2: 4 };
+    let (data, _) = world.get_world(win);
+    let live: Vec<(i64, i64)> = (0..data.len() as i64)
+        .filter(|i| data[*i as usize] == 1)
+        .map(|i| (win.x1 + i % 7, win.y1 + i / 7))
+        .collect();
+    assert_eq!(live, vec![(0, -1), (1, -1), (0, 1), (1, 1)]);
+}
+
+#[test]
+fn hensel_notation() {
+    let rule = Rule::parse("B2-a/S12").unwrap();
+    assert_eq!(rule.to_string(), "B2-a/S12");
+    assert_eq!(Rule::parse("B3/S2-i34q").unwrap().to_string(), "B3/S2-i34q");
+    assert_eq!(Rule::parse("B2cekn/S").unwrap().to_string(), "B2-ai/S");
+    assert_eq!(Rule::parse("B3-cekainyqjr/S23").unwrap().to_string(), "B/S23");
+    // Listing every configuration is the same as the plain count
+    let full = "B3cekainyqjr/S2cekain3cekainyqjr";
+    assert_eq!(Rule::parse(full), Ok(Rule::life()));
+    let full = "B4cekainyqjrtwz5cekainyqjr/S1ce6cekain7ce";
+    assert_eq!(Rule::parse(full), Rule::parse("B45/S167"));
+    assert!(Rule::parse("B2z/S23").is_err());
+    assert!(Rule::parse("B2-/S23").is_err());
+}
+
+#[test]
+fn hensel_configuration_decides_birth() {
+    // B2-a: two neighbours in a straight line give birth, adjacent ones do not
+    let rule = Rule::parse("B2-a/S").unwrap();
+    let mut world = World::new();
+    world.set_rule(rule);
+    world.set_cell(0, 0, true);
+    world.set_cell(2, 0, true);
+    world.life_step();
+    let win = Vec4 { x1: -3, y1: -3, x2: 6, y2: 4 };
+    let (data, _) = world.get_world(win);
+    // The gap sees 2i, cells above and below it see 2c
+    for y in -1..=1 {
+        assert_eq!(data[(1 - win.x1 + (y - win.y1) * 9) as usize], 1);
+    }
+    assert_eq!(data.iter().filter(|c| **c == 1).count(), 3);
+
+    let mut world = World::new();
+    world.set_rule(rule);
+    world.set_cell(0, 0, true);
+    world.set_cell(1, 0, true);
+    world.life_step();
+    let (data, _) = world.get_world(win);
+    // Cells touching the domino see 2a and stay dead
+    assert!(data.iter().all(|c| *c == 0));
+}
+
+#[test]
+fn generations_notation() {
+    let brain = Rule::parse("B2/S/C3").unwrap();
+    assert_eq!(brain.states(), 3);
+    assert_eq!(Rule::parse("/2/3"), Ok(brain));
+    assert_eq!(brain.to_string(), "B2/S/C3");
+    assert_eq!(brain.name(), Some("Brian's Brain"));
+    assert_eq!(Rule::parse("345/2/4").unwrap().name(), Some("Star Wars"));
+    assert!(Rule::parse("B2/S/C1").is_err());
+    assert!(Rule::parse("B2/S/C300").is_err());
+}
+
+#[test]
+fn brians_brain_cells_decay() {
+    let mut world = World::new();
+    world.set_rule(Rule::parse("B2/S/C3").unwrap());
+    world.set_cell(0, 0, true);
+    world.set_cell(1, 0, true);
+    let win = Vec4 { x1: -1, y1: -2, x2: 3, y2: 3 };
+    let state = |data: &Vec<u8>, x: i64, y: i64| data[(x - win.x1 + (y - win.y1) * 4) as usize];
+    world.life_step();
+    let (data, _) = world.get_world(win);
+    assert_eq!(state(&data, 0, 0), 2);
+    assert_eq!(state(&data, 1, 0), 2);
+    for (x, y) in [(0, -1), (1, -1), (0, 1), (1, 1)] {
+        assert_eq!(state(&data, x, y), 1);
+    }
+    world.life_step();
+    let (data, _) = world.get_world(win);
+    // Dying cells are gone and don't count as neighbours
+    assert_eq!(state(&data, 0, 0), 0);
+    assert_eq!(state(&data, 1, 0), 0);
+    assert_eq!(state(&data, 0, -1), 2);
+    assert_eq!(state(&data, 0, -2), 1);
+}
@@ -0,0 +1,39 @@
+use gol::{base64, clear_rect, fill_rect, osc52, span, LifeEngine, Pattern, Vec4, World};
+
+#[test]
+fn spans_any_corner_order() {
+    let rect = Vec4 { x1: -2, y1: 1, x2: 4, y2: 6 };
+    assert_eq!(span((-2, 1), (3, 5)), rect);
+    assert_eq!(span((3, 5), (-2, 1)), rect);
+    assert_eq!(span((3, 1), (-2, 5)), rect);
+    assert_eq!(span((0, 0), (0, 0)).size().x, 1);
+}
+
+#[test]
+fn fill_clear_and_copy() {
+    let mut world = World::new();
+    world.set_cell(10, 10, true);
+    let rect = span((0, 0), (4, 2));
+    fill_rect(&mut world, rect, 1);
+    assert_eq!(world.population(), 16);
+    let pattern = Pattern::from_world(&world, rect);
+    assert_eq!((pattern.width, pattern.height, pattern.population()), (5, 3, 15));
+
+    clear_rect(&mut world, span((1, 1), (8, 8)));
+    assert_eq!(world.population(), 15 - 8 + 1);
+    assert_eq!(world.get_cell(10, 10), 1);
+
+    pattern.place(&mut world, 20, 20);
+    assert_eq!(world.get_cell(24, 22), 1);
+    assert_eq!(world.population(), 8 + 15);
+}
+
+#[test]
+fn base64_padding() {
+    assert_eq!(base64(b""), "");
+    assert_eq!(base64(b"f"), "Zg==");
+    assert_eq!(base64(b"fo"), "Zm8=");
+    assert_eq!(base64(b"foo"), "Zm9v");
+    assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    assert_eq!(osc52("x = 1"), "\x1b]52;c;eCA9IDE=\x07");
+}
@@ -0,0 +1,51 @@
+use gol::{Rule, Session, Vec4, World};
+
+fn sorted(mut cells: Vec<(i64, i64, u8)>) -> Vec<(i64, i64, u8)> {
+    cells.sort();
+    cells
+}
+
+fn session(engine: &str) -> Session {
+    Session {
+        engine: engine.to_string(),
+        window: Vec4 { x1: -40, y1: 7, x2: 80, y2: 37 },
+        step_exp: 5,
+        speed: 64,
+        playing: true,
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut world = World::new();
+    world.set_rule(Rule::parse("B36/S23").unwrap());
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2), (-500, 90)] {
+        world.set_cell(x, y, true);
+    }
+    for _ in 0..7 {
+        world.life_step();
+    }
+    for engine in ["CHUNKS", "HASHLIFE", "DENSE"] {
+        let text = session(engine).write(&world);
+        let (read, restored) = Session::read(&text).unwrap();
+        assert_eq!(read, session(engine));
+        assert_eq!(restored.name(), engine);
+        assert_eq!(restored.rule(), world.rule());
+        assert_eq!(restored.generation(), 7);
+        assert_eq!(sorted(restored.live_cells()), sorted(world.live_cells()));
+    }
+}
+
+#[test]
+fn invalid_sessions() {
+    let cells = "[M2]\n.*$\n";
+    for text in [
+        "gol session\nengine CHUNKS\n".to_string(),
+        format!("engine CHUNKS\n{}", cells),
+        format!("gol session\nengine FAST\n{}", cells),
+        format!("gol session\nengine DENSE\nwindow 1 2 3\n{}", cells),
+        format!("gol session\nengine DENSE\nplaying maybe\n{}", cells),
+    ] {
+        assert!(Session::read(&text).is_err(), "{:?}", text);
+    }
+}
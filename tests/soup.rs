@@ -0,0 +1,51 @@
+use gol::{place_soup, soup, HashLife, LifeEngine, Pattern, Vec4, World, SOUP_SIZE};
+
+fn square(x: i64, y: i64) -> Vec4<i64> {
+    Vec4 { x1: x, y1: y, x2: x + SOUP_SIZE, y2: y + SOUP_SIZE }
+}
+
+#[test]
+fn seeds_are_reproducible() {
+    let mut a = World::new();
+    let mut b = World::new();
+    a.fill_random(square(0, 0), 0.5, 42);
+    b.fill_random(square(100, -7), 0.5, 42);
+    let rect = square(0, 0);
+    assert_eq!(Pattern::from_world(&a, rect).cells, Pattern::from_world(&b, square(100, -7)).cells);
+    assert_eq!(Pattern::from_world(&a, rect).cells, soup(SOUP_SIZE, SOUP_SIZE, 0.5, 42).cells);
+    assert_ne!(soup(16, 16, 0.5, 42).cells, soup(16, 16, 0.5, 43).cells);
+}
+
+#[test]
+fn density() {
+    assert_eq!(soup(64, 64, 0.0, 1).population(), 0);
+    assert_eq!(soup(64, 64, 1.0, 1).population(), 64 * 64);
+    let population = soup(64, 64, 0.25, 9).population() as f64;
+    assert!((population / (64.0 * 64.0) - 0.25).abs() < 0.03, "{}", population);
+}
+
+#[test]
+fn soup_replaces_the_rectangle() {
+    let mut world = World::new();
+    world.set_history(true);
+    world.set_cell(5, 5, true);
+    world.set_cell(SOUP_SIZE, 0, true);
+    world.fill_random(square(0, 0), 0.0, 3);
+    assert_eq!(world.population(), 1);
+    world.commit_edit();
+    world.fill_random(square(0, 0), 0.5, 3);
+    assert!(world.population() > 1);
+    world.commit_edit();
+    assert!(world.undo());
+    assert_eq!(world.population(), 1);
+}
+
+#[test]
+fn any_engine_gets_the_same_soup() {
+    let mut world = World::new();
+    world.fill_random(square(-3, 4), 0.5, 77);
+    let mut life = HashLife::new();
+    life.set_cell(0, 10, true);
+    place_soup(&mut life, square(-3, 4), 0.5, 77);
+    assert_eq!(Pattern::from_world(&life, square(-3, 4)).cells, Pattern::from_world(&world, square(-3, 4)).cells);
+}
@@ -0,0 +1,76 @@
+use gol::{bounding_box, soup, sparkline, LifeEngine, Rule, StepStats, Vec4, World, POPULATION_HISTORY};
+
+fn soup_world(rule: &str) -> World {
+    let mut world = World::new();
+    world.set_rule(Rule::parse(rule).unwrap());
+    soup(48, 48, 0.4, 5).place(&mut world, -20, -30);
+    world
+}
+
+#[test]
+fn births_and_deaths() {
+    let mut world = World::new();
+    for x in 0..3 {
+        world.set_cell(x, 0, true);
+    }
+    world.life_step();
+    assert_eq!(world.stats().last_step, StepStats { births: 2, deaths: 2 });
+
+    for rule in ["B3/S23", "B2/S345/C4"] {
+        let mut world = soup_world(rule);
+        for _ in 0..50 {
+            let population = world.population();
+            world.life_step();
+            let step = world.stats().last_step;
+            assert_eq!(world.population(), population + step.births - step.deaths, "{}", rule);
+            assert_eq!(world.population(), world.live_cells().len() as u64, "{}", rule);
+        }
+    }
+}
+
+#[test]
+fn bounding_box_matches_cells() {
+    let mut world = soup_world("B3/S23");
+    for _ in 0..30 {
+        world.life_step();
+        assert_eq!(world.bounding_box(), bounding_box(&world.live_cells()));
+    }
+    let mut world = World::new();
+    assert_eq!(world.bounding_box(), None);
+    world.set_cell(-65, 130, true);
+    world.set_cell(63, -1, true);
+    assert_eq!(world.bounding_box(), Some(Vec4 { x1: -65, y1: -1, x2: 64, y2: 131 }));
+}
+
+#[test]
+fn population_history() {
+    let mut world = soup_world("B3/S23");
+    world.set_history(true);
+    for _ in 0..20 {
+        world.life_step();
+    }
+    let history = &world.stats().history;
+    assert_eq!(history.len(), 20);
+    assert_eq!(history.back(), Some(&(20, world.population())));
+    // Undone generations are replaced when stepping again
+    for _ in 0..5 {
+        world.undo();
+    }
+    world.life_step();
+    assert_eq!(world.stats().history.len(), 16);
+    assert_eq!(world.stats().history.back().unwrap().0, 16);
+
+    world.set_history(false);
+    for _ in 0..POPULATION_HISTORY {
+        world.life_step();
+    }
+    assert_eq!(world.stats().history.len(), POPULATION_HISTORY);
+}
+
+#[test]
+fn sparklines() {
+    assert_eq!(sparkline(&[], 10), "");
+    assert_eq!(sparkline(&[3, 3, 3], 10), "▁▁▁");
+    assert_eq!(sparkline(&[0, 7, 14], 10), "▁▄█");
+    assert_eq!(sparkline(&[100, 0, 1, 2, 3, 4, 5, 6, 7], 8), "▁▂▃▄▅▆▇█");
+}
@@ -0,0 +1,155 @@
+use gol::{soup, HashLife, LifeEngine, Rule, Timeline, World};
+
+fn sorted(mut cells: Vec<(i64, i64, u8)>) -> Vec<(i64, i64, u8)> {
+    cells.sort();
+    cells
+}
+
+fn r_pentomino(world: &mut dyn LifeEngine) {
+    for (x, y) in [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)] {
+        world.set_cell(x, y, true);
+    }
+}
+
+#[test]
+fn restore_recorded_generations() {
+    let mut world = World::new();
+    r_pentomino(&mut world);
+    let mut timeline = Timeline::new(100, usize::MAX);
+    let mut generations = Vec::new();
+    for _ in 0..60 {
+        timeline.record(&world);
+        generations.push(sorted(world.live_cells()));
+        world.life_step();
+    }
+    assert_eq!(timeline.len(), 60);
+    for (index, cells) in generations.iter().enumerate() {
+        let restored = timeline.restore(index, &world).unwrap();
+        assert_eq!(restored.name(), "CHUNKS");
+        assert_eq!(restored.generation(), index as u64);
+        assert_eq!(&sorted(restored.live_cells()), cells);
+    }
+    assert!(timeline.restore(60, &world).is_none());
+}
+
+#[test]
+fn oldest_generations_are_dropped() {
+    let mut life = HashLife::new();
+    r_pentomino(&mut life);
+    let mut timeline = Timeline::new(10, usize::MAX);
+    for _ in 0..25 {
+        timeline.record(&life);
+        life.life_step();
+    }
+    assert_eq!(timeline.len(), 10);
+    assert_eq!(timeline.generation(0), Some(15));
+    assert_eq!(timeline.position(24), Some(9));
+    let restored = timeline.restore(0, &life).unwrap();
+    assert_eq!(restored.name(), "HASHLIFE");
+    assert_eq!(restored.generation(), 15);
+}
+
+#[test]
+fn rewinding_drops_later_generations() {
+    let mut world = World::new();
+    r_pentomino(&mut world);
+    let mut timeline = Timeline::new(100, usize::MAX);
+    for _ in 0..20 {
+        timeline.record(&world);
+        world.life_step();
+    }
+    let mut rewound = timeline.restore(5, &world).unwrap();
+    rewound.set_cell(30, 30, true);
+    timeline.record(rewound.as_ref());
+    assert_eq!(timeline.len(), 6);
+    rewound.life_step();
+    timeline.record(rewound.as_ref());
+    assert_eq!(timeline.generation(6), Some(6));
+    assert_eq!(timeline.restore(5, rewound.as_ref()).unwrap().get_cell(30, 30), 1);
+}
+
+#[test]
+fn generations_states_and_empty_worlds() {
+    let mut world = World::new();
+    world.set_rule(Rule::parse("B2/S/C3").unwrap());
+    world.set_cell(0, 0, true);
+    world.set_cell(1, 0, true);
+    let mut timeline = Timeline::new(100, usize::MAX);
+    let mut generations = Vec::new();
+    for _ in 0..4 {
+        timeline.record(&world);
+        generations.push(sorted(world.live_cells()));
+        world.life_step();
+    }
+    for (index, cells) in generations.iter().enumerate() {
+        assert_eq!(&sorted(timeline.restore(index, &world).unwrap().live_cells()), cells);
+    }
+    let mut empty = Timeline::new(5, usize::MAX);
+    empty.record(&World::new());
+    assert_eq!(empty.restore(0, &world).unwrap().population(), 0);
+}
+
+#[test]
+fn frames_keep_their_rule() {
+    let mut world = World::new();
+    world.set_rule(Rule::parse("B2/S/C3").unwrap());
+    world.set_cell(0, 0, true);
+    world.set_cell(1, 0, true);
+    let mut timeline = Timeline::new(100, usize::MAX);
+    timeline.record(&world);
+    world.life_step();
+    world.set_rule(Rule::life());
+    timeline.record(&world);
+    let restored = timeline.restore(0, &world).unwrap();
+    assert_eq!(restored.rule(), Rule::parse("B2/S/C3").unwrap());
+    assert_eq!(timeline.restore(1, &world).unwrap().rule(), Rule::life());
+}
+
+#[test]
+fn byte_limit_drops_oldest_generations() {
+    let mut world = World::new();
+    r_pentomino(&mut world);
+    let mut timeline = Timeline::new(1000, 300);
+    for _ in 0..100 {
+        timeline.record(&world);
+        world.life_step();
+    }
+    assert!(timeline.len() < 100);
+    assert!(timeline.memory() <= 300);
+    assert_eq!(timeline.generation(timeline.len() - 1), Some(99));
+
+    // Newest generation stays however large it is
+    let mut tiny = Timeline::new(1000, 1);
+    tiny.record(&world);
+    tiny.record(&world);
+    assert_eq!(tiny.len(), 1);
+}
+
+#[test]
+fn quadtree_frames() {
+    let mut life = HashLife::new();
+    soup(64, 64, 0.4, 3).place(&mut life, -32, -32);
+    life.set_rule(Rule::parse("B36/S23").unwrap());
+    let mut timeline = Timeline::new(5, usize::MAX);
+    for _ in 0..150 {
+        life.step_pow2(4);
+        timeline.record(&life);
+    }
+    assert!(life.step_pow2(10));
+    timeline.record(&life);
+    let restored = timeline.restore(4, &life).unwrap();
+    assert_eq!(restored.name(), "HASHLIFE");
+    assert_eq!(restored.generation(), life.generation());
+    assert_eq!(restored.rule(), life.rule());
+    assert_eq!(sorted(restored.live_cells()), sorted(life.live_cells()));
+    let previous = timeline.restore(3, &life).unwrap();
+    assert_eq!(previous.generation(), 2400);
+
+    // Quadtree frames restored into an engine without one
+    let mut world = World::new();
+    world.set_rule(Rule::life());
+    let restored = timeline.restore(4, &world).unwrap();
+    assert_eq!(restored.name(), "CHUNKS");
+    assert_eq!(restored.rule(), life.rule());
+    assert_eq!(sorted(restored.live_cells()), sorted(life.live_cells()));
+}
@@ -0,0 +1,65 @@
+use gol::{ellipse, line, rectangle, Brush, BrushShape, Tool};
+
+fn adjacent(a: (i64, i64), b: (i64, i64)) -> bool {
+    (a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1
+}
+
+#[test]
+fn lines_have_no_gaps() {
+    for to in [(0, 0), (7, 2), (-3, 11), (-9, -9), (5, -1), (0, 6)] {
+        let cells = line((1, 1), to);
+        assert_eq!(cells.first(), Some(&(1, 1)));
+        assert_eq!(cells.last(), Some(&to));
+        let len = (to.0 - 1).abs().max((to.1 - 1).abs()) + 1;
+        assert_eq!(cells.len() as i64, len, "line to {:?}", to);
+        for pair in cells.windows(2) {
+            assert!(adjacent(pair[0], pair[1]), "gap in line to {:?}", to);
+        }
+    }
+}
+
+#[test]
+fn rectangles() {
+    let filled = rectangle((4, 3), (0, 0), true);
+    assert_eq!(filled.len(), 5 * 4);
+    let hollow = rectangle((0, 0), (4, 3), false);
+    assert_eq!(hollow.len(), 2 * 5 + 2 * 2);
+    assert!(hollow.iter().all(|c| filled.contains(c)));
+    assert_eq!(rectangle((2, 2), (2, 2), false), vec![(2, 2)]);
+}
+
+#[test]
+fn ellipses() {
+    let filled = ellipse((0, 0), (10, 6), true);
+    let hollow = ellipse((0, 0), (10, 6), false);
+    assert!(!hollow.is_empty() && hollow.len() < filled.len());
+    assert!(hollow.iter().all(|c| filled.contains(c)));
+    for &(x, y) in filled.iter() {
+        assert!((0..=10).contains(&x) && (0..=6).contains(&y));
+        // Mirrored on both axes
+        assert!(filled.contains(&(10 - x, y)) && filled.contains(&(x, 6 - y)));
+    }
+    for (x, y) in [(5, 0), (5, 6), (0, 3), (10, 3)] {
+        assert!(hollow.contains(&(x, y)), "{} {}", x, y);
+    }
+}
+
+#[test]
+fn brushes() {
+    let mut brush = Brush::new();
+    assert_eq!(brush.stamp((3, 3)), vec![(3, 3)]);
+    brush.size = 3;
+    assert_eq!(brush.stamp((0, 0)).len(), 9);
+    brush.shape = BrushShape::Round;
+    brush.size = 5;
+    let round = brush.stamp((0, 0));
+    assert!(round.len() < 25 && round.contains(&(0, 0)) && round.contains(&(2, 0)));
+    assert!(!round.contains(&(2, 2)));
+    // Overlapping stamps are only painted once
+    let painted = brush.paint(&Tool::Line.shape((0, 0), (3, 0)));
+    let mut unique = painted.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(painted.len(), unique.len());
+    assert_eq!(Tool::Pen.shape((0, 0), (5, 5)), vec![(5, 5)]);
+}